@@ -1,7 +1,9 @@
 use crate::config;
 use crate::physics;
 use crate::physics::Entity;
+use serde::{Deserialize, Serialize};
 
+#[derive(Serialize, Deserialize)]
 pub struct World(pub Vec<Entity>);
 
 impl World {
@@ -25,24 +27,99 @@ impl World {
     pub fn to_refs(&self) -> Vec<&Entity> {
         self.0.iter().collect()
     }
+
+    pub fn index_of(&self, id: u32) -> Option<usize> {
+        self.0.iter().position(|boid| boid.id == id)
+    }
 }
 
-pub fn step_world<F>(world: &World, config: &config::Config, goal_functions: &[F]) -> World
+/// `pulls` are added to each boid's resultant goal after the `goal_functions` are averaged, so that
+/// they act at full strength where they apply and leave the other goals alone where they don't.
+/// They're given no neighbours.
+/// Radians, centred on the heading, within which a boid sees others. Boids see all round.
+pub const FIELD_OF_VIEW: f32 = 2.0 * std::f32::consts::PI;
+
+pub fn step_world<F>(
+    world: &World,
+    config: &config::Config,
+    goal_functions: &[F],
+    pulls: &[F],
+) -> World
 where
     F: Fn(&physics::Entity, &[&Entity]) -> goals::Goal,
 {
     world.map_with_rest_of_world(|boid, entities| {
-        let resultant_goal = goals::resultant_goal(
+        let goals::ResultantGoal(averaged) = goals::resultant_goal(
             boid,
             entities,
             goals::InfluenceRadius(config.influence_radius),
             goal_functions,
         );
+        let resultant_goal = goals::ResultantGoal(add_pulls(boid, averaged, pulls));
 
         strategies::v1(boid, resultant_goal, config.max_ang_vel, config.max_force)
     })
 }
 
+/// The averaged goal `resultant` with `pulls` added, as `step_world` steers by
+pub fn add_pulls<F>(
+    boid: &Entity,
+    mut resultant: nalgebra_glm::Vec2,
+    pulls: &[F],
+) -> nalgebra_glm::Vec2
+where
+    F: Fn(&physics::Entity, &[&Entity]) -> goals::Goal,
+{
+    for pull in pulls {
+        let goals::Goal(pull) = pull(boid, &[]);
+        // Even adding zero can flip the sign of a zero component, and with it the steering
+        if pull != nalgebra_glm::zero() {
+            resultant += pull;
+        }
+    }
+
+    resultant
+}
+
+/// Everyone but the boid at `index`
+fn others(world: &World, index: usize) -> Vec<&Entity> {
+    world
+        .0
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| i != index)
+        .map(|(_, other)| other)
+        .collect()
+}
+
+/// Every goal's contribution for the boid at `index`, as `step_world` would work them out
+pub fn goal_contributions<F>(
+    world: &World,
+    index: usize,
+    config: &config::Config,
+    goal_functions: &[F],
+) -> Vec<nalgebra_glm::Vec2>
+where
+    F: Fn(&physics::Entity, &[&Entity]) -> goals::Goal,
+{
+    goals::contributions(
+        &world.0[index],
+        &others(world, index),
+        goals::InfluenceRadius(config.influence_radius),
+        goal_functions,
+    )
+}
+
+/// Ids of the boids the boid at `index` is influenced by this step
+pub fn neighbours(world: &World, index: usize, config: &config::Config) -> Vec<u32> {
+    let boid = &world.0[index];
+    others(world, index)
+        .into_iter()
+        .filter(|other| nalgebra_glm::distance(&other.pos, &boid.pos) <= config.influence_radius)
+        .map(|other| other.id)
+        .collect()
+}
+
 pub fn origin_at_boid(boid: &Entity, boids: &[&Entity]) -> World {
     let mut result = Vec::new();
     for b in boids {
@@ -58,6 +135,7 @@ pub mod goals {
     use crate::boids::*;
     use crate::utilities::*;
     use nalgebra_glm as na;
+    use serde::{Deserialize, Serialize};
 
     /// 0 <= magnitude <= 1
     pub struct Goal(pub na::Vec2);
@@ -69,23 +147,76 @@ pub mod goals {
     /// Must never be zero
     pub struct InfluenceRadius(pub f32);
 
+    pub type GoalFunction = Box<dyn Fn(&Entity, &[&Entity]) -> Goal>;
+
+    /// Weights a neighbour's contribution by its distance from the boid.
+    /// Every kernel is 1 at the boid itself, and all but `Uniform` fall towards 0 at the radius.
+    #[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+    #[serde(rename_all = "snake_case")]
+    pub enum Kernel {
+        Uniform,
+        Linear,
+        /// Softened so that it stays finite for coincident boids
+        InverseSquare,
+        /// sigma = radius / 3
+        Gaussian,
+        Smoothstep,
+    }
+
+    impl Kernel {
+        pub fn weight(self, distance: f32, InfluenceRadius(radius): InfluenceRadius) -> f32 {
+            let x = (distance / radius).clamp(0.0, 1.0);
+
+            match self {
+                Kernel::Uniform => 1.0,
+                Kernel::Linear => 1.0 - x,
+                Kernel::InverseSquare => 1.0 / (1.0 + (x / 0.1).powi(2)),
+                Kernel::Gaussian => (-4.5 * x * x).exp(),
+                Kernel::Smoothstep => 1.0 - x * x * (3.0 - 2.0 * x),
+            }
+        }
+    }
+
+    fn kernel_weights(boids: &[&Entity], radius: InfluenceRadius, kernel: Kernel) -> Vec<f32> {
+        boids
+            .iter()
+            .map(|boid| kernel.weight(na::magnitude(&boid.pos), radius))
+            .collect()
+    }
+
     pub fn resultant_goal<F>(
         boid: &Entity,
         other_boids: &[&Entity],
         radius: InfluenceRadius,
         goal_functions: &[F],
     ) -> ResultantGoal
+    where
+        F: Fn(&Entity, &[&Entity]) -> Goal,
+    {
+        ResultantGoal(mean(&contributions(
+            boid,
+            other_boids,
+            radius,
+            goal_functions,
+        )))
+    }
+
+    /// Each goal function's goal for the boid, in order, before they're averaged
+    pub fn contributions<F>(
+        boid: &Entity,
+        other_boids: &[&Entity],
+        radius: InfluenceRadius,
+        goal_functions: &[F],
+    ) -> Vec<na::Vec2>
     where
         F: Fn(&Entity, &[&Entity]) -> Goal,
     {
         let influential_boids = region_of_influence(boid, other_boids, radius);
 
-        let goals: Vec<na::Vec2> = goal_functions
+        goal_functions
             .iter()
             .map(|f| f(boid, &influential_boids.to_refs()).0)
-            .collect();
-
-        ResultantGoal(mean(&goals))
+            .collect()
     }
 
     pub fn region_of_influence(
@@ -103,37 +234,67 @@ pub mod goals {
         )
     }
 
-    pub fn center_of_mass(boids: &[&Entity], InfluenceRadius(radius): InfluenceRadius) -> Goal {
+    pub fn center_of_mass(boids: &[&Entity], radius: InfluenceRadius, kernel: Kernel) -> Goal {
         let positions: Vec<na::Vec2> = boids.iter().map(|boid| boid.pos).collect();
-        Goal(mean(&positions) / radius)
+        let weights = kernel_weights(boids, radius, kernel);
+
+        Goal(faded_weighted_mean(&positions, &weights) / radius.0)
     }
 
+    /// Each neighbour inside the adjusted radius, `radius * distance_coefficient`, pushes with up to
+    /// `radius`, weighted by `kernel` over the adjusted radius so that every kernel but `Uniform`
+    /// fades to nothing at the cut-off. A neighbour exactly on top of the boid pushes along
+    /// `tie_break` instead.
     pub fn keep_distance(
+        boid: &Entity,
         boids: &[&Entity],
-        InfluenceRadius(radius): InfluenceRadius,
+        radius: InfluenceRadius,
         distance_coefficient: f32,
+        kernel: Kernel,
     ) -> Goal {
-        let positions = boids.iter().map(|boid| boid.pos);
-        let adjusted_radius = radius * distance_coefficient;
+        let adjusted_radius = InfluenceRadius(radius.0 * distance_coefficient);
 
         Goal(
-            positions
-                .map(|pos| {
-                    let magnitude = na::magnitude(&pos);
-                    if magnitude > adjusted_radius {
+            boids
+                .iter()
+                .map(|other| {
+                    let magnitude = na::magnitude(&other.pos);
+                    let away = if other.pos == na::zero() {
+                        tie_break(boid.id, other.id)
+                    } else {
+                        normalize_or_zero(&-other.pos)
+                    };
+
+                    if magnitude > adjusted_radius.0 {
                         na::zero()
                     } else {
-                        (radius - na::magnitude(&pos)) * na::normalize(&-pos)
+                        radius.0 * kernel.weight(magnitude, adjusted_radius) * away
                     }
                 })
                 .sum(),
         )
     }
 
-    pub fn same_direction(boids: &[&Entity], InfluenceRadius(radius): InfluenceRadius) -> Goal {
+    /// A direction for boid `id` to move away from coincident boid `other_id` along, opposite to
+    /// the one `other_id` gets, so that the pair always splits the same way. Zero for the same id.
+    fn tie_break(id: u32, other_id: u32) -> na::Vec2 {
+        const GOLDEN_ANGLE: f32 = 2.399_963;
+        let (low, high) = (id.min(other_id), id.max(other_id));
+        let angle = (low as f32 + high as f32 * GOLDEN_ANGLE) % (2.0 * std::f32::consts::PI);
+        let direction = na::vec2(angle.cos(), angle.sin());
+
+        match id.cmp(&other_id) {
+            std::cmp::Ordering::Less => direction,
+            std::cmp::Ordering::Greater => -direction,
+            std::cmp::Ordering::Equal => na::zero(),
+        }
+    }
+
+    pub fn same_direction(boids: &[&Entity], radius: InfluenceRadius, kernel: Kernel) -> Goal {
         let rotations: Vec<na::Vec2> = boids.iter().map(|boid| boid.rot).collect();
+        let weights = kernel_weights(boids, radius, kernel);
 
-        Goal(mean(&rotations) / radius)
+        Goal(faded_weighted_mean(&rotations, &weights) / radius.0)
     }
 
     pub fn bound(boid: &Entity, dimensions: na::Vec2, min_dist: f32) -> Goal {
@@ -162,7 +323,52 @@ pub mod goals {
 
     pub fn static_goal(boid: &Entity, position: na::Vec2) -> Goal {
         let relative_position = position - boid.pos;
-        Goal(na::normalize(&relative_position))
+        Goal(normalize_or_zero(&relative_position))
+    }
+
+    /// `static_goal`, fading linearly from full strength at `position` to nothing at `falloff` away
+    pub fn static_goal_with_falloff(boid: &Entity, position: na::Vec2, falloff: f32) -> Goal {
+        let distance = na::distance(&boid.pos, &position);
+        if distance >= falloff {
+            Goal(na::zero())
+        } else {
+            Goal(static_goal(boid, position).0 * (1.0 - distance / falloff))
+        }
+    }
+
+    /// Steers directly away from a circular obstacle, from 0 at `falloff` beyond its edge up to 1 at the edge
+    pub fn avoid_obstacle(boid: &Entity, centre: na::Vec2, radius: f32, falloff: f32) -> Goal {
+        let away = boid.pos - centre;
+        let distance_from_edge = na::magnitude(&away) - radius;
+
+        if distance_from_edge >= falloff {
+            Goal(na::zero())
+        } else if distance_from_edge <= 0.0 {
+            Goal(normalize_or_zero(&away))
+        } else {
+            Goal(normalize_or_zero(&away) * (1.0 - distance_from_edge / falloff))
+        }
+    }
+
+    /// Reynolds' wander: steer towards a point on a circle projected `distance` ahead of the boid.
+    /// The point drifts around the circle as `update_wander` jitters `wander_angle`.
+    pub fn wander(boid: &Entity, distance: f32, radius: f32) -> Goal {
+        let heading = normalize_or_zero(&boid.rot);
+        let displacement = radius * na::rotate_vec2(&heading, boid.wander_angle);
+
+        Goal(normalize_or_zero(&(heading * distance + displacement)))
+    }
+
+    /// Moves every boid's wander target by at most `jitter` radians
+    pub fn update_wander(World(boids): &mut World, jitter: f32, rng: &mut impl rand::Rng) {
+        if jitter <= 0.0 {
+            return;
+        }
+
+        for boid in boids {
+            boid.wander_angle += rng.gen_range(-jitter, jitter);
+            boid.wander_angle %= 2.0 * std::f32::consts::PI;
+        }
     }
 
     mod tests {
@@ -193,11 +399,207 @@ pub mod goals {
                 let origin_boid: Entity = Entity { pos: na::zero(), .. Default::default() };
                 let world = World(boids);
                 let influential_boids = region_of_influence(&origin_boid, &world.to_refs(), InfluenceRadius(2.0));
-                let Goal(goal) = center_of_mass(&influential_boids.to_refs(), InfluenceRadius(2.0));
+                let Goal(goal) = center_of_mass(&influential_boids.to_refs(), InfluenceRadius(2.0), Kernel::Uniform);
 
                 assert!(na::magnitude(&goal) <= 1.0);
             }
         }
+
+        proptest! {
+            #[test]
+            fn test_wander_bounded(
+                rot in vec2(na::vec2(-1.0, -1.0), na::vec2(1.0, 1.0))
+                    .prop_filter("Headings must be non-zero".to_owned(), |v| na::magnitude(v) > 0.01),
+                wander_angle in -7.0..7.0f32,
+                distance in 0.0..2.0f32,
+                radius in 0.0..2.0f32,
+            ) {
+                let boid = Entity { rot, wander_angle, .. Default::default() };
+                let Goal(goal) = wander(&boid, distance, radius);
+
+                assert!(na::magnitude(&goal) <= 1.0 + 1e-5);
+            }
+        }
+
+        proptest! {
+            #[test]
+            fn test_kernel_weights_fall_off(
+                radius in 0.1..3.0f32,
+                near in 0.0..1.0f32,
+                far in 0.0..1.0f32,
+            ) {
+                let (near, far) = if near <= far { (near, far) } else { (far, near) };
+
+                for kernel in &[Kernel::Uniform, Kernel::Linear, Kernel::InverseSquare, Kernel::Gaussian, Kernel::Smoothstep] {
+                    let near_weight = kernel.weight(near * radius, InfluenceRadius(radius));
+                    let far_weight = kernel.weight(far * radius, InfluenceRadius(radius));
+
+                    assert!(near_weight <= 1.0 && far_weight >= 0.0);
+                    assert!(near_weight >= far_weight);
+                    assert!((kernel.weight(0.0, InfluenceRadius(radius)) - 1.0).abs() < 1e-6);
+                }
+            }
+        }
+
+        proptest! {
+            #[test]
+            fn test_goals_finite_for_coincident_boids(
+                pos in vec2(na::vec2(-1.0, -1.0), na::vec2(1.0, 1.0)),
+                wander_angle in -7.0..7.0f32,
+            ) {
+                let boid = Entity { pos, rot: na::zero(), wander_angle, .. Default::default() };
+                let world = World(vec![boid; 3]);
+                let radius = InfluenceRadius(0.5);
+                let influential_boids = region_of_influence(&boid, &world.to_refs(), radius);
+                let neighbours = influential_boids.to_refs();
+
+                let goals = [
+                    static_goal(&boid, pos),
+                    keep_distance(&boid, &neighbours, radius, 0.025, Kernel::Linear),
+                    center_of_mass(&neighbours, radius, Kernel::Gaussian),
+                    same_direction(&neighbours, radius, Kernel::Uniform),
+                    wander(&boid, 1.0, 0.5),
+                    avoid_obstacle(&boid, pos, 0.2, 0.0),
+                ];
+
+                for Goal(goal) in goals.iter() {
+                    assert!(goal.x.is_finite() && goal.y.is_finite());
+                }
+            }
+        }
+
+        #[test]
+        fn test_neighbour_goals_fade_out_at_the_cut_off() {
+            let radius = InfluenceRadius(0.5);
+            let boid = Entity::default();
+            let at = |distance: f32| Entity {
+                id: 1,
+                pos: na::vec2(distance, 0.0),
+                rot: na::vec2(0.0, 1.0),
+                ..Default::default()
+            };
+            let cut_off = radius.0 * 0.025;
+
+            for kernel in &[
+                Kernel::Linear,
+                Kernel::InverseSquare,
+                Kernel::Gaussian,
+                Kernel::Smoothstep,
+            ] {
+                let separation = |distance: f32| {
+                    na::magnitude(&keep_distance(&boid, &[&at(distance)], radius, 0.025, *kernel).0)
+                };
+                assert!(
+                    separation(cut_off * 0.999) < 0.05 * radius.0,
+                    "{:?}",
+                    kernel
+                );
+                assert_eq!(separation(cut_off * 1.001), 0.0);
+
+                let edge = at(radius.0 * 0.999);
+                let Goal(cohesion) = center_of_mass(&[&edge], radius, *kernel);
+                let Goal(alignment) = same_direction(&[&edge], radius, *kernel);
+                assert!(na::magnitude(&cohesion) < 0.05, "{:?}", kernel);
+                assert!(na::magnitude(&alignment) < 0.05 / radius.0, "{:?}", kernel);
+            }
+        }
+
+        #[test]
+        fn test_coincident_boids_separate() {
+            let radius = InfluenceRadius(0.5);
+            let a = Entity {
+                id: 3,
+                ..Default::default()
+            };
+            let b = Entity { id: 8, ..a };
+
+            let Goal(a_goal) = keep_distance(&a, &[&b], radius, 0.025, Kernel::Linear);
+            let Goal(b_goal) = keep_distance(&b, &[&a], radius, 0.025, Kernel::Linear);
+            assert!(na::magnitude(&a_goal) > 0.0);
+            assert!(na::distance(&a_goal, &-b_goal) < 1e-6);
+
+            let Goal(again) = keep_distance(&a, &[&b], radius, 0.025, Kernel::Linear);
+            assert_eq!(again, a_goal);
+        }
+    }
+}
+
+/// The Cucker–Smale model: each boid accelerates towards its neighbours' velocities,
+/// weighted by psi(r) = K / (1 + r^2)^beta. Velocities reach consensus for any initial state
+/// when beta < 1/2. Positions are left unbounded, as in the original model.
+pub mod cucker_smale {
+    use crate::boids::World;
+    use crate::physics::Entity;
+    use nalgebra_glm as na;
+
+    pub fn communication_weight(distance: f32, k: f32, beta: f32) -> f32 {
+        k / (1.0 + distance * distance).powf(beta)
+    }
+
+    pub fn acceleration(boid: &Entity, other_boids: &[&Entity], k: f32, beta: f32) -> na::Vec2 {
+        let population = (other_boids.len() + 1) as f32;
+
+        other_boids
+            .iter()
+            .map(|other| {
+                let distance = na::distance(&boid.pos, &other.pos);
+                communication_weight(distance, k, beta) * (other.vel - boid.vel)
+            })
+            .sum::<na::Vec2>()
+            / population
+    }
+
+    pub fn step_world(world: &World, k: f32, beta: f32, delta_time: f32) -> World {
+        world.map_with_rest_of_world(|boid, other_boids| {
+            boid.vel += acceleration(boid, other_boids, k, beta) * delta_time;
+            boid.pos += boid.vel * delta_time;
+            if boid.vel != na::zero() {
+                boid.rot = na::normalize(&boid.vel);
+            }
+        })
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::generation;
+        use rand::Rng;
+
+        fn velocity_spread(World(boids): &World) -> f32 {
+            let velocities: Vec<na::Vec2> = boids.iter().map(|boid| boid.vel).collect();
+            let mean = crate::utilities::mean(&velocities);
+
+            velocities
+                .iter()
+                .map(|vel| na::distance(vel, &mean))
+                .fold(0.0, f32::max)
+        }
+
+        #[test]
+        fn test_velocity_consensus() {
+            let mut rng = generation::seeded_rng(7);
+            let mut world =
+                generation::random_world(20, na::vec2(-1.0, -1.0), na::vec2(1.0, 1.0), &mut rng);
+            for boid in world.0.iter_mut() {
+                boid.vel = na::vec2(rng.gen_range(-1.0, 1.0), rng.gen_range(-1.0, 1.0));
+            }
+
+            let initial_spread = velocity_spread(&world);
+            let initial_momentum: na::Vec2 = world.0.iter().map(|boid| boid.vel).sum();
+
+            for _ in 0..2000 {
+                world = step_world(&world, 1.0, 0.25, 0.05);
+            }
+
+            let momentum: na::Vec2 = world.0.iter().map(|boid| boid.vel).sum();
+            assert!(initial_spread > 0.5);
+            assert!(
+                velocity_spread(&world) < 1e-3,
+                "{}",
+                velocity_spread(&world)
+            );
+            assert!(na::distance(&momentum, &initial_momentum) < 1e-3);
+        }
     }
 }
 
@@ -239,7 +641,12 @@ pub mod strategies {
         max_ang_vel: f32,
         max_force: f32,
     ) {
-        let angle = steering_angle(&g, &boid.rot);
+        // Without a goal there's nowhere to turn to, so carry on the way the boid is heading
+        let angle = if g == na::zero() {
+            0.0
+        } else {
+            steering_angle(&g, &boid.rot)
+        };
         let angle_coefficient = -(angle / PI);
         let force_coefficient = (PI - angle.abs()) / PI;
 
@@ -338,6 +745,21 @@ pub mod strategies {
             }
         }
 
+        #[test]
+        fn test_v1_keeps_heading_without_goal() {
+            for rot in &[na::vec2(1.0, 0.0), na::vec2(0.0, -1.0), na::vec2(-0.6, 0.8)] {
+                let mut boid = Entity {
+                    rot: *rot,
+                    ..Default::default()
+                };
+
+                v1(&mut boid, ResultantGoal(na::zero()), 1.0, 1.0);
+
+                assert_eq!(boid.angular_vel, 0.0);
+                assert_eq!(boid.resultant_force, na::zero());
+            }
+        }
+
         #[test]
         fn unit_test_v1() {
             for (g, expected_av, expected_f) in &[
@@ -363,3 +785,142 @@ pub mod strategies {
         }
     }
 }
+
+/// The Vicsek model: every boid moves at a constant speed, and at each step takes the average
+/// heading of its neighbours (itself included) plus uniform angular noise in [-noise / 2, noise / 2].
+/// The arena is periodic, so that the model matches the literature.
+pub mod vicsek {
+    use crate::boids::goals::InfluenceRadius;
+    use crate::boids::World;
+    use crate::config;
+    use crate::generation;
+    use crate::metrics::polarisation;
+    use nalgebra_glm as na;
+    use rand::Rng;
+
+    const ARENA_SIZE: f32 = 2.0;
+
+    /// Shortest displacement from `a` to `b` in the periodic arena
+    pub fn periodic_displacement(a: &na::Vec2, b: &na::Vec2) -> na::Vec2 {
+        (b - a).map(|d| d - ARENA_SIZE * (d / ARENA_SIZE).round())
+    }
+
+    pub fn wrap(pos: na::Vec2) -> na::Vec2 {
+        pos.map(|x| (x + ARENA_SIZE / 2.0).rem_euclid(ARENA_SIZE) - ARENA_SIZE / 2.0)
+    }
+
+    pub fn step_world(
+        World(boids): &mut World,
+        InfluenceRadius(radius): InfluenceRadius,
+        speed: f32,
+        noise: f32,
+        delta_time: f32,
+        rng: &mut impl Rng,
+    ) {
+        let headings: Vec<f32> = boids
+            .iter()
+            .map(|boid| {
+                let neighbour_rot: na::Vec2 = boids
+                    .iter()
+                    .filter(|other| {
+                        na::magnitude(&periodic_displacement(&boid.pos, &other.pos)) <= radius
+                    })
+                    .map(|other| other.rot)
+                    .sum();
+
+                neighbour_rot.y.atan2(neighbour_rot.x) + noise * rng.gen_range(-0.5, 0.5)
+            })
+            .collect();
+
+        for (boid, heading) in boids.iter_mut().zip(headings) {
+            boid.rot = na::vec2(heading.cos(), heading.sin());
+            boid.vel = boid.rot * speed;
+            boid.angular_vel = 0.0;
+            boid.pos = wrap(boid.pos + boid.vel * delta_time);
+        }
+    }
+
+    /// Runs the model once for each noise amplitude, starting from the same seeded world,
+    /// and reports the polarisation averaged over the last quarter of `steps`, or at least the
+    /// last step. Runs at least one step.
+    pub fn noise_sweep(
+        config: &config::Config,
+        noises: &[f32],
+        steps: u32,
+        delta_time: f32,
+    ) -> Vec<(f32, f32)> {
+        noises
+            .iter()
+            .map(|&noise| {
+                let mut rng = generation::seeded_rng(config.seed);
+                let mut world = generation::random_world(
+                    config.population,
+                    na::vec2(-1.0, -1.0),
+                    na::vec2(1.0, 1.0),
+                    &mut rng,
+                );
+
+                let steps = steps.max(1);
+                let measured_from = steps - (steps / 4).max(1);
+                let mut total = 0.0;
+                for step in 0..steps {
+                    step_world(
+                        &mut world,
+                        InfluenceRadius(config.influence_radius),
+                        config.vicsek_speed,
+                        noise,
+                        delta_time,
+                        &mut rng,
+                    );
+                    if step >= measured_from {
+                        total += polarisation(&world);
+                    }
+                }
+
+                (noise, total / (steps - measured_from) as f32)
+            })
+            .collect()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::test_utils::*;
+        use proptest::prelude::*;
+        use std::f32::consts::PI;
+
+        proptest! {
+            #[test]
+            fn test_wrap_stays_in_arena(pos in vec2(na::vec2(-5.0, -5.0), na::vec2(5.0, 5.0))) {
+                let wrapped = wrap(pos);
+
+                assert!(wrapped.x >= -1.0 && wrapped.x < 1.0 + 1e-5);
+                assert!(wrapped.y >= -1.0 && wrapped.y < 1.0 + 1e-5);
+                assert!(na::magnitude(&periodic_displacement(&pos, &wrapped)) < 1e-4);
+            }
+        }
+
+        #[test]
+        fn test_noise_sweep_phase_transition() {
+            let config = config::Config {
+                population: 100,
+                influence_radius: 0.4,
+                vicsek_speed: 0.5,
+                ..test_config()
+            };
+
+            let sweep = noise_sweep(&config, &[0.1, 2.0 * PI], 400, 0.016);
+
+            assert!(sweep[0].1 > 0.9, "ordered phase: {:?}", sweep);
+            assert!(sweep[1].1 < 0.3, "disordered phase: {:?}", sweep);
+        }
+
+        #[test]
+        fn test_noise_sweep_few_steps() {
+            for steps in 0..4 {
+                let sweep = noise_sweep(&test_config(), &[0.5], steps, 0.016);
+                assert!(sweep[0].1.is_finite(), "{} steps: {:?}", steps, sweep);
+            }
+        }
+    }
+}
@@ -0,0 +1,330 @@
+//! Maps world coordinates to the window, keeping the world's aspect ratio whatever the window's shape.
+//!
+//! The mouse wheel zooms about the cursor and dragging with the middle button pans. Following the
+//! flock's centroid or a boid (the selected one, else the one nearest the cursor), and resetting the
+//! view, are bound in `[keys]`.
+
+use crate::boids::World;
+use crate::input::Action;
+use crate::metrics;
+use gl::glutin;
+use glium as gl;
+use nalgebra_glm as na;
+
+/// How much one notch of the mouse wheel zooms
+const ZOOM_PER_LINE: f32 = 0.9;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Follow {
+    Nothing,
+    Centroid,
+    Boid(u32),
+}
+
+pub struct Camera {
+    /// World position at the middle of the window
+    pub centre: na::Vec2,
+    /// Half the height of the window, in world units
+    pub half_height: f32,
+    pub follow: Follow,
+    /// Logical pixels
+    window_size: na::Vec2,
+    cursor: na::Vec2,
+    dragging: bool,
+}
+
+impl Camera {
+    /// Shows the whole arena, from -1 to 1 on the shorter axis
+    pub fn new(window_size: na::Vec2) -> Camera {
+        Camera {
+            centre: na::zero(),
+            half_height: 1.0,
+            follow: Follow::Nothing,
+            window_size,
+            cursor: window_size / 2.0,
+            dragging: false,
+        }
+        .reset()
+    }
+
+    fn reset(self) -> Camera {
+        Camera {
+            centre: na::zero(),
+            half_height: (1.0 / self.aspect()).max(1.0),
+            follow: Follow::Nothing,
+            ..self
+        }
+    }
+
+    /// Width over height
+    pub fn aspect(&self) -> f32 {
+        self.window_size.x / self.window_size.y.max(1.0)
+    }
+
+    /// World to clip space
+    pub fn view_projection(&self) -> na::Mat4 {
+        na::scaling(&na::vec3(
+            1.0 / (self.half_height * self.aspect()),
+            1.0 / self.half_height,
+            1.0,
+        )) * na::translation(&na::vec3(-self.centre.x, -self.centre.y, 0.0))
+    }
+
+    /// Logical pixels, with the origin at the top left, to clip space. For drawing over the world.
+    pub fn screen_projection(&self) -> na::Mat4 {
+        na::translation(&na::vec3(-1.0, 1.0, 0.0))
+            * na::scaling(&na::vec3(
+                2.0 / self.window_size.x,
+                -2.0 / self.window_size.y,
+                1.0,
+            ))
+    }
+
+    pub fn window_size(&self) -> na::Vec2 {
+        self.window_size
+    }
+
+    /// From logical pixels, with the origin at the top left
+    pub fn window_to_world(&self, position: na::Vec2) -> na::Vec2 {
+        let clip = na::vec2(
+            2.0 * position.x / self.window_size.x - 1.0,
+            1.0 - 2.0 * position.y / self.window_size.y,
+        );
+
+        self.centre
+            + na::vec2(
+                clip.x * self.half_height * self.aspect(),
+                clip.y * self.half_height,
+            )
+    }
+
+    pub fn cursor_in_world(&self) -> na::Vec2 {
+        self.window_to_world(self.cursor)
+    }
+
+    /// Scales the view by `factor`, keeping the world point under `position` where it is
+    pub fn zoom(&mut self, factor: f32, position: na::Vec2) {
+        let fixed = self.window_to_world(position);
+        self.half_height *= factor;
+        self.centre = fixed - (fixed - self.centre) * factor;
+    }
+
+    /// Moves the world by `pixels`, as if dragged
+    pub fn pan(&mut self, pixels: na::Vec2) {
+        let world_per_pixel = 2.0 * self.half_height / self.window_size.y;
+        self.centre -= na::vec2(pixels.x, -pixels.y) * world_per_pixel;
+        self.follow = Follow::Nothing;
+    }
+
+    pub fn handle_event(&mut self, event: &glutin::Event) {
+        let event = match event {
+            glutin::Event::WindowEvent { event, .. } => event,
+            _ => return,
+        };
+
+        match event {
+            glutin::WindowEvent::Resized(size) => {
+                self.window_size = na::vec2(size.width as f32, size.height as f32);
+            }
+            glutin::WindowEvent::CursorMoved { position, .. } => {
+                let cursor = na::vec2(position.x as f32, position.y as f32);
+                if self.dragging {
+                    self.pan(cursor - self.cursor);
+                }
+                self.cursor = cursor;
+            }
+            glutin::WindowEvent::MouseInput {
+                state,
+                button: glutin::MouseButton::Middle,
+                ..
+            } => {
+                self.dragging = *state == glutin::ElementState::Pressed;
+            }
+            glutin::WindowEvent::MouseWheel { delta, .. } => {
+                let lines = match delta {
+                    glutin::MouseScrollDelta::LineDelta(_, y) => *y,
+                    glutin::MouseScrollDelta::PixelDelta(position) => position.y as f32 / 20.0,
+                };
+                self.zoom(ZOOM_PER_LINE.powf(lines), self.cursor);
+            }
+            _ => (),
+        }
+    }
+
+    /// Ignores actions that aren't the camera's. Following a boid picks `selected` if it's still
+    /// around, otherwise the boid nearest the cursor.
+    pub fn handle_action(&mut self, action: Action, world: &World, selected: Option<u32>) {
+        match action {
+            Action::FollowCentroid => {
+                self.follow = match self.follow {
+                    Follow::Centroid => Follow::Nothing,
+                    _ => Follow::Centroid,
+                }
+            }
+            Action::FollowBoid => {
+                let target = selected
+                    .filter(|&id| world.index_of(id).is_some())
+                    .or_else(|| nearest_boid(world, self.cursor_in_world()));
+                self.follow = match (self.follow, target) {
+                    (Follow::Boid(_), _) | (_, None) => Follow::Nothing,
+                    (_, Some(id)) => Follow::Boid(id),
+                }
+            }
+            Action::ResetCamera => *self = Camera { ..*self }.reset(),
+            _ => (),
+        }
+    }
+
+    /// Moves to whatever is being followed. Stops following a boid once it's gone.
+    pub fn update(&mut self, world: &World) {
+        match self.follow {
+            Follow::Nothing => (),
+            Follow::Centroid => {
+                if !world.0.is_empty() {
+                    self.centre = metrics::centroid(world);
+                }
+            }
+            Follow::Boid(id) => match world.index_of(id) {
+                Some(index) => self.centre = world.0[index].pos,
+                None => self.follow = Follow::Nothing,
+            },
+        }
+    }
+}
+
+/// Id of the boid closest to `position`
+pub fn nearest_boid(World(boids): &World, position: na::Vec2) -> Option<u32> {
+    boids
+        .iter()
+        .min_by(|a, b| {
+            na::distance(&a.pos, &position)
+                .partial_cmp(&na::distance(&b.pos, &position))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })
+        .map(|boid| boid.id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::Entity;
+    use assert_approx_eq::*;
+
+    fn camera() -> Camera {
+        Camera::new(na::vec2(1024.0, 768.0))
+    }
+
+    fn to_clip(camera: &Camera, world: na::Vec2) -> na::Vec2 {
+        (camera.view_projection() * na::vec4(world.x, world.y, 0.0, 1.0)).xy()
+    }
+
+    #[test]
+    fn test_aspect_ratio_kept() {
+        let camera = camera();
+        let corner = to_clip(&camera, na::vec2(1.0, 1.0));
+
+        assert_approx_eq!(corner.y, 1.0);
+        assert_approx_eq!(corner.x, 768.0 / 1024.0);
+
+        let tall = Camera::new(na::vec2(500.0, 1000.0));
+        assert_approx_eq!(to_clip(&tall, na::vec2(1.0, 1.0)).x, 1.0);
+    }
+
+    #[test]
+    fn test_window_to_world_inverts_view_projection() {
+        let mut camera = camera();
+        camera.centre = na::vec2(0.3, -0.2);
+        camera.half_height = 0.5;
+
+        let world = camera.window_to_world(na::vec2(100.0, 700.0));
+        let clip = to_clip(&camera, world);
+        assert_approx_eq!(clip.x, 2.0 * 100.0 / 1024.0 - 1.0);
+        assert_approx_eq!(clip.y, 1.0 - 2.0 * 700.0 / 768.0);
+        assert_eq!(
+            camera.window_to_world(na::vec2(512.0, 384.0)),
+            camera.centre
+        );
+    }
+
+    #[test]
+    fn test_screen_projection_corners() {
+        let mut camera = camera();
+        camera.zoom(0.5, na::vec2(10.0, 10.0));
+        let to_clip = |pixels: na::Vec2| {
+            (camera.screen_projection() * na::vec4(pixels.x, pixels.y, 0.0, 1.0)).xy()
+        };
+
+        assert_eq!(to_clip(na::zero()), na::vec2(-1.0, 1.0));
+        assert_eq!(to_clip(na::vec2(1024.0, 768.0)), na::vec2(1.0, -1.0));
+    }
+
+    #[test]
+    fn test_zoom_keeps_point_under_cursor() {
+        let mut camera = camera();
+        let cursor = na::vec2(800.0, 100.0);
+        let before = camera.window_to_world(cursor);
+
+        camera.zoom(0.5, cursor);
+        let after = camera.window_to_world(cursor);
+
+        assert_approx_eq!(camera.half_height, 0.5);
+        assert_approx_eq!(before.x, after.x);
+        assert_approx_eq!(before.y, after.y);
+    }
+
+    #[test]
+    fn test_pan_follows_cursor() {
+        let mut camera = camera();
+        camera.follow = Follow::Centroid;
+        let grabbed = camera.window_to_world(na::vec2(200.0, 200.0));
+
+        camera.pan(na::vec2(50.0, -30.0));
+
+        let moved = camera.window_to_world(na::vec2(250.0, 170.0));
+        assert_approx_eq!(grabbed.x, moved.x);
+        assert_approx_eq!(grabbed.y, moved.y);
+        assert_eq!(camera.follow, Follow::Nothing);
+    }
+
+    #[test]
+    fn test_follow_boid() {
+        let boid = |id, x| Entity {
+            id,
+            pos: na::vec2(x, 0.5),
+            ..Default::default()
+        };
+        let mut world = World(vec![boid(4, -0.5), boid(9, 0.5)]);
+        let mut camera = camera();
+
+        assert_eq!(nearest_boid(&world, na::vec2(0.4, 0.0)), Some(9));
+        camera.follow = Follow::Boid(9);
+        camera.update(&world);
+        assert_eq!(camera.centre, na::vec2(0.5, 0.5));
+
+        world.0.pop();
+        camera.update(&world);
+        assert_eq!(camera.follow, Follow::Nothing);
+    }
+
+    #[test]
+    fn test_follow_prefers_the_selected_boid() {
+        let boid = |id, x| Entity {
+            id,
+            pos: na::vec2(x, 0.0),
+            ..Default::default()
+        };
+        let world = World(vec![boid(4, -0.5), boid(9, 0.5)]);
+        let mut camera = camera();
+        camera.cursor = na::vec2(0.0, 384.0);
+        assert_eq!(nearest_boid(&world, camera.cursor_in_world()), Some(4));
+
+        camera.handle_action(Action::FollowBoid, &world, Some(9));
+        assert_eq!(camera.follow, Follow::Boid(9));
+
+        camera.handle_action(Action::FollowBoid, &world, Some(9));
+        assert_eq!(camera.follow, Follow::Nothing);
+
+        camera.handle_action(Action::FollowBoid, &world, Some(17));
+        assert_eq!(camera.follow, Follow::Boid(4));
+    }
+}
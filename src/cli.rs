@@ -0,0 +1,180 @@
+use std::path::PathBuf;
+
+/// Command line options shared by the windowed and headless runs
+pub struct Args {
+    /// Run without a window, for `steps` steps
+    pub headless: bool,
+    pub steps: u64,
+    /// Print flock metrics every step
+    pub metrics: bool,
+    /// Print the size, centroid and mean heading of every group each step
+    pub groups: bool,
+    /// Record a trajectory to this file
+    pub record: Option<PathBuf>,
+    /// Only record every Nth step
+    pub record_every: u64,
+    /// Headless only: write boid state as CSV rows to this file
+    pub csv: Option<PathBuf>,
+    /// Headless only: write boid state as a (steps, boids, fields) NumPy array to this file
+    pub npy: Option<PathBuf>,
+    /// Headless only: write flock metrics as CSV rows to this file
+    pub metrics_csv: Option<PathBuf>,
+    /// Only export every Nth step
+    pub export_every: u64,
+    /// Start from the initial conditions in a scenario file
+    pub scenario: Option<PathBuf>,
+    /// Start from a snapshot, including its config, instead of a new world
+    pub load: Option<PathBuf>,
+    /// Play back a recorded trajectory in the window instead of simulating
+    pub replay: Option<PathBuf>,
+    /// Print the Vicsek polarisation for a range of noise amplitudes, each run for `steps` steps
+    pub vicsek_sweep: bool,
+    /// Headless only: print a checksum and summary of the world every step, in the golden file format
+    pub checksum: bool,
+    /// Headless only: compare every step against this golden file, failing at the first difference
+    pub golden: Option<PathBuf>,
+    /// Headless only: compare against the golden file within this tolerance rather than exactly
+    pub golden_tolerance: Option<f32>,
+}
+
+pub const USAGE: &str =
+    "usage: boids [--headless] [--steps N] [--metrics] [--groups] [--checksum] [--record FILE [--record-every N]] [--scenario FILE] [--load SNAPSHOT] [--replay FILE]\n       [--csv FILE] [--npy FILE] [--metrics-csv FILE] [--export-every N] [--vicsek-sweep]\n       [--golden FILE [--golden-tolerance T]]";
+
+impl Default for Args {
+    fn default() -> Args {
+        Args {
+            headless: false,
+            steps: 1000,
+            metrics: false,
+            groups: false,
+            record: None,
+            record_every: 1,
+            csv: None,
+            npy: None,
+            metrics_csv: None,
+            export_every: 1,
+            scenario: None,
+            load: None,
+            replay: None,
+            vicsek_sweep: false,
+            checksum: false,
+            golden: None,
+            golden_tolerance: None,
+        }
+    }
+}
+
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--headless" => parsed.headless = true,
+            "--metrics" => parsed.metrics = true,
+            "--groups" => parsed.groups = true,
+            "--vicsek-sweep" => parsed.vicsek_sweep = true,
+            "--checksum" => parsed.checksum = true,
+            "--steps" => {
+                parsed.steps = value(&arg, args.next())?
+                    .parse()
+                    .map_err(|_| "--steps expects a whole number".to_owned())?
+            }
+            "--record" => parsed.record = Some(value(&arg, args.next())?.into()),
+            "--record-every" => {
+                parsed.record_every = value(&arg, args.next())?
+                    .parse()
+                    .map_err(|_| "--record-every expects a whole number".to_owned())?
+            }
+            "--csv" => parsed.csv = Some(value(&arg, args.next())?.into()),
+            "--npy" => parsed.npy = Some(value(&arg, args.next())?.into()),
+            "--metrics-csv" => parsed.metrics_csv = Some(value(&arg, args.next())?.into()),
+            "--export-every" => {
+                parsed.export_every = value(&arg, args.next())?
+                    .parse()
+                    .map_err(|_| "--export-every expects a whole number".to_owned())?
+            }
+            "--scenario" => parsed.scenario = Some(value(&arg, args.next())?.into()),
+            "--load" => parsed.load = Some(value(&arg, args.next())?.into()),
+            "--replay" => parsed.replay = Some(value(&arg, args.next())?.into()),
+            "--golden" => parsed.golden = Some(value(&arg, args.next())?.into()),
+            "--golden-tolerance" => {
+                parsed.golden_tolerance = Some(
+                    value(&arg, args.next())?
+                        .parse()
+                        .map_err(|_| "--golden-tolerance expects a number".to_owned())?,
+                )
+            }
+            _ => return Err(format!("unknown argument {}", arg)),
+        }
+    }
+
+    let headless_only = [
+        ("--csv", parsed.csv.is_some()),
+        ("--npy", parsed.npy.is_some()),
+        ("--metrics-csv", parsed.metrics_csv.is_some()),
+        ("--checksum", parsed.checksum),
+        ("--golden", parsed.golden.is_some()),
+        ("--golden-tolerance", parsed.golden_tolerance.is_some()),
+    ];
+    if let Some((flag, _)) = headless_only
+        .iter()
+        .find(|(_, given)| *given && !parsed.headless)
+    {
+        return Err(format!("{} only works with --headless", flag));
+    }
+
+    Ok(parsed)
+}
+
+fn value(flag: &str, value: Option<String>) -> Result<String, String> {
+    value.ok_or_else(|| format!("{} expects a value", flag))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Result<Args, String> {
+        parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse() {
+        let parsed = args(&["--headless", "--steps", "20", "--metrics"]).unwrap();
+        assert!(parsed.headless && parsed.metrics);
+        assert_eq!(parsed.steps, 20);
+
+        assert!(!args(&[]).unwrap().headless);
+        assert!(args(&["--steps"]).is_err());
+        assert!(args(&["--steps", "many"]).is_err());
+        assert!(args(&["--fast"]).is_err());
+
+        let parsed = args(&[
+            "--headless",
+            "--golden",
+            "golden/boids.txt",
+            "--golden-tolerance",
+            "1e-4",
+        ])
+        .unwrap();
+        assert_eq!(parsed.golden, Some(PathBuf::from("golden/boids.txt")));
+        assert_eq!(parsed.golden_tolerance, Some(1e-4));
+    }
+
+    #[test]
+    fn test_exports_need_headless() {
+        assert!(args(&["--csv", "out.csv"]).is_err());
+        assert!(args(&["--npy", "out.npy"]).is_err());
+        assert!(args(&["--metrics-csv", "metrics.csv"]).is_err());
+        assert!(args(&["--checksum"]).is_err());
+        assert_eq!(
+            args(&["--golden", "golden/boids.txt"]).err(),
+            Some("--golden only works with --headless".to_owned())
+        );
+        assert!(args(&["--golden-tolerance", "1e-4"]).is_err());
+
+        let parsed = args(&["--csv", "out.csv", "--headless"]).unwrap();
+        assert_eq!(parsed.csv, Some(PathBuf::from("out.csv")));
+    }
+}
@@ -0,0 +1,95 @@
+//! Decides how many fixed steps to run each frame, so the simulation can be paused, stepped
+//! once at a time, or run faster or slower than real time without changing `DELTA_TIME`.
+
+use crate::simulation::DELTA_TIME;
+
+const MIN_TIME_SCALE: f32 = 1.0 / 16.0;
+const MAX_TIME_SCALE: f32 = 16.0;
+/// Stops a slow frame being followed by ever more steps to catch up
+const MAX_STEPS_PER_FRAME: u32 = 32;
+
+#[derive(Debug)]
+pub struct Clock {
+    /// Simulated seconds per real second
+    pub time_scale: f32,
+    pub paused: bool,
+    /// Simulated time not yet stepped
+    accumulated: f32,
+    /// Steps asked for one at a time while paused
+    single_steps: u32,
+}
+
+impl Clock {
+    pub fn new() -> Clock {
+        Clock {
+            time_scale: 1.0,
+            paused: false,
+            accumulated: 0.0,
+            single_steps: 0,
+        }
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        self.accumulated = 0.0;
+    }
+
+    /// Pauses, if running, and runs exactly one more step
+    pub fn step_once(&mut self) {
+        self.paused = true;
+        self.accumulated = 0.0;
+        self.single_steps += 1;
+    }
+
+    pub fn faster(&mut self) {
+        self.time_scale = (self.time_scale * 2.0).min(MAX_TIME_SCALE);
+    }
+
+    pub fn slower(&mut self) {
+        self.time_scale = (self.time_scale / 2.0).max(MIN_TIME_SCALE);
+    }
+
+    /// The number of steps to run after `elapsed` real seconds
+    pub fn advance(&mut self, elapsed: f32) -> u32 {
+        if self.paused {
+            return std::mem::take(&mut self.single_steps);
+        }
+
+        self.accumulated += elapsed * self.time_scale;
+        let steps = (self.accumulated / DELTA_TIME).floor() as u32;
+        self.accumulated -= steps as f32 * DELTA_TIME;
+        if steps > MAX_STEPS_PER_FRAME {
+            self.accumulated = 0.0;
+        }
+
+        steps.min(MAX_STEPS_PER_FRAME)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_scale_and_pause() {
+        let mut clock = Clock::new();
+        assert_eq!(clock.advance(DELTA_TIME * 0.5), 0);
+        assert_eq!(clock.advance(DELTA_TIME * 0.6), 1);
+
+        clock.faster();
+        assert_eq!(clock.advance(DELTA_TIME * 2.0), 4);
+        assert_eq!(clock.advance(100.0), MAX_STEPS_PER_FRAME);
+
+        clock.toggle_pause();
+        assert_eq!(clock.advance(1.0), 0);
+        clock.step_once();
+        clock.step_once();
+        assert_eq!(clock.advance(1.0), 2);
+        assert_eq!(clock.advance(1.0), 0);
+
+        for _ in 0..10 {
+            clock.slower();
+        }
+        assert_eq!(clock.time_scale, MIN_TIME_SCALE);
+    }
+}
@@ -1,9 +1,18 @@
+use crate::boids::goals::Kernel;
+use crate::generation::Spawn;
+use crate::geometry::{ColourBy, Palette};
+use crate::groups::Grouping;
+use crate::input::Keys;
+use crate::simulation::Rule;
 use hotwatch::*;
+use nalgebra_glm as na;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::sync::mpsc;
 
-#[derive(Serialize, Deserialize)]
+/// Everything added since the first six fields has a default, the value boids.toml ships with,
+/// so that older config files, and the configs inside older snapshots and recordings, still load.
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Config {
     pub influence_radius: f32,
     pub drag_coefficient: f32,
@@ -11,25 +20,291 @@ pub struct Config {
     pub max_force: f32,
     pub max_ang_vel: f32,
     pub behaviour_active: bool,
+    #[serde(default = "defaults::cohesion_kernel")]
+    pub cohesion_kernel: Kernel,
+    /// Weighted over separation's own short cut-off rather than the influence radius, so unlike
+    /// the baseline's linear falloff, which still pushed at nearly full strength at the cut-off,
+    /// `Linear` now fades to nothing there
+    #[serde(default = "defaults::separation_kernel")]
+    pub separation_kernel: Kernel,
+    #[serde(default = "defaults::alignment_kernel")]
+    pub alignment_kernel: Kernel,
+    #[serde(default = "defaults::seed")]
+    pub seed: u64,
+    #[serde(default = "defaults::wander_distance")]
+    pub wander_distance: f32,
+    #[serde(default = "defaults::wander_radius")]
+    pub wander_radius: f32,
+    /// Largest change in a boid's wander angle per step, in radians
+    #[serde(default = "defaults::wander_jitter")]
+    pub wander_jitter: f32,
+    #[serde(default = "defaults::wander_weight")]
+    pub wander_weight: f32,
+    #[serde(default = "defaults::rule")]
+    pub rule: Rule,
+    #[serde(default = "defaults::vicsek_speed")]
+    pub vicsek_speed: f32,
+    /// Width of the uniform angular noise, in radians
+    #[serde(default = "defaults::vicsek_noise")]
+    pub vicsek_noise: f32,
+    #[serde(default = "defaults::cucker_smale_k")]
+    pub cucker_smale_k: f32,
+    #[serde(default = "defaults::cucker_smale_beta")]
+    pub cucker_smale_beta: f32,
+    /// Speed along `rot` that every boid starts with under the Vicsek and Cucker–Smale rules.
+    /// Under the boids rule they start at rest.
+    #[serde(default = "defaults::initial_speed")]
+    pub initial_speed: f32,
+    #[serde(default = "defaults::grouping")]
+    pub grouping: Grouping,
+    #[serde(default = "defaults::dbscan_min_points")]
+    pub dbscan_min_points: usize,
+    /// Largest angle between two boids' headings, in radians, for DBSCAN to treat them as neighbours
+    #[serde(default = "defaults::dbscan_max_heading_angle")]
+    pub dbscan_max_heading_angle: f32,
+    #[serde(default = "defaults::colour_by")]
+    pub colour_by: ColourBy,
+    #[serde(default = "defaults::palette")]
+    pub palette: Palette,
+    #[serde(default = "defaults::spawn")]
+    pub spawn: Spawn,
+    /// Closest two boids may start with `PoissonDisk` spawning. Too wide for the population and
+    /// fewer boids start than asked for, with a warning.
+    #[serde(default = "defaults::spawn_spacing")]
+    pub spawn_spacing: f32,
+    /// Fraction of a grid cell
+    #[serde(default = "defaults::spawn_jitter")]
+    pub spawn_jitter: f32,
+    #[serde(default = "defaults::spawn_centre")]
+    pub spawn_centre: na::Vec2,
+    #[serde(default = "defaults::spawn_spread")]
+    pub spawn_spread: f32,
+    /// Check every boid after each step, panicking with the first invalid one
+    #[serde(default)]
+    pub check_invariants: bool,
+    /// Whether trails are shown at startup. They can be toggled while running.
+    #[serde(default)]
+    pub trails: bool,
+    /// Steps of history kept for each boid's trail
+    #[serde(default = "defaults::trail_length")]
+    pub trail_length: usize,
+    #[serde(default)]
+    pub overlay_velocity: bool,
+    #[serde(default)]
+    pub overlay_drag: bool,
+    /// Net force: steering plus drag
+    #[serde(default)]
+    pub overlay_force: bool,
+    #[serde(default)]
+    pub overlay_influence: bool,
+    /// The sector each boid sees, out to the influence radius
+    #[serde(default)]
+    pub overlay_field_of_view: bool,
+    /// One arrow per goal function, showing what it asks of the boid
+    #[serde(default)]
+    pub overlay_goals: bool,
+    /// Only draw overlays for the selected boid, rather than every boid
+    #[serde(default = "defaults::overlay_selected_only")]
+    pub overlay_selected_only: bool,
+    /// How strongly a held mouse button attracts or repels boids. The pull is added on top of the
+    /// averaged goals, so 1 at the cursor matches the strongest any single goal can be.
+    #[serde(default = "defaults::pointer_weight")]
+    pub pointer_weight: f32,
+    /// Distance from the cursor at which its pull fades to nothing
+    #[serde(default = "defaults::pointer_falloff")]
+    pub pointer_falloff: f32,
+    /// Boids spawned by a shift-click
+    #[serde(default = "defaults::spawn_burst")]
+    pub spawn_burst: u32,
+    /// Half the width of the square a burst is spawned over
+    #[serde(default = "defaults::spawn_burst_radius")]
+    pub spawn_burst_radius: f32,
+    /// Whether the text HUD is shown at startup. It can be toggled while running.
+    #[serde(default = "defaults::hud")]
+    pub hud: bool,
+    #[serde(default)]
+    pub keys: Keys,
+}
+
+mod defaults {
+    use super::*;
+
+    pub fn cohesion_kernel() -> Kernel {
+        Kernel::Uniform
+    }
+
+    pub fn separation_kernel() -> Kernel {
+        Kernel::Linear
+    }
+
+    pub fn alignment_kernel() -> Kernel {
+        Kernel::Uniform
+    }
+
+    pub fn seed() -> u64 {
+        1
+    }
+
+    pub fn wander_distance() -> f32 {
+        1.0
+    }
+
+    pub fn wander_radius() -> f32 {
+        0.5
+    }
+
+    pub fn wander_jitter() -> f32 {
+        0.3
+    }
+
+    pub fn wander_weight() -> f32 {
+        0.5
+    }
+
+    pub fn rule() -> Rule {
+        Rule::Boids
+    }
+
+    pub fn vicsek_speed() -> f32 {
+        0.3
+    }
+
+    pub fn vicsek_noise() -> f32 {
+        0.5
+    }
+
+    pub fn cucker_smale_k() -> f32 {
+        1.0
+    }
+
+    pub fn cucker_smale_beta() -> f32 {
+        0.25
+    }
+
+    pub fn initial_speed() -> f32 {
+        0.3
+    }
+
+    pub fn grouping() -> Grouping {
+        Grouping::Components
+    }
+
+    pub fn dbscan_min_points() -> usize {
+        3
+    }
+
+    pub fn dbscan_max_heading_angle() -> f32 {
+        0.8
+    }
+
+    pub fn colour_by() -> ColourBy {
+        ColourBy::Speed
+    }
+
+    pub fn palette() -> Palette {
+        Palette::Viridis
+    }
+
+    pub fn spawn() -> Spawn {
+        Spawn::Uniform
+    }
+
+    pub fn spawn_spacing() -> f32 {
+        0.15
+    }
+
+    pub fn spawn_jitter() -> f32 {
+        0.5
+    }
+
+    pub fn spawn_centre() -> na::Vec2 {
+        na::zero()
+    }
+
+    pub fn spawn_spread() -> f32 {
+        0.3
+    }
+
+    pub fn trail_length() -> usize {
+        60
+    }
+
+    pub fn overlay_selected_only() -> bool {
+        true
+    }
+
+    pub fn pointer_weight() -> f32 {
+        0.4
+    }
+
+    pub fn pointer_falloff() -> f32 {
+        0.5
+    }
+
+    pub fn spawn_burst() -> u32 {
+        10
+    }
+
+    pub fn spawn_burst_radius() -> f32 {
+        0.05
+    }
+
+    pub fn hud() -> bool {
+        true
+    }
 }
 
 pub fn read(path: &str) -> Config {
-    toml::from_slice(&fs::read(path).expect("Config file doesn't exist."))
-        .expect("Config file unreadable.")
+    let config: Config = toml::from_slice(&fs::read(path).expect("Config file doesn't exist."))
+        .expect("Config file unreadable.");
+    config
+        .keys
+        .validate()
+        .expect("Config file binds a key twice.");
+
+    config
+}
+
+pub fn try_read(path: &str) -> Result<Config, String> {
+    let contents = fs::read(path).map_err(|error| error.to_string())?;
+    let config: Config = toml::from_slice(&contents).map_err(|error| error.to_string())?;
+    config.keys.validate()?;
+
+    Ok(config)
 }
 
-pub fn watch(path: String) -> (mpsc::Receiver<Config>, Hotwatch) {
+/// Sends the config each time the file is written, or why it couldn't be read
+pub fn watch(path: String) -> (mpsc::Receiver<Result<Config, String>>, Hotwatch) {
     let (sender, receiver) = mpsc::channel();
     let mut hotwatch = Hotwatch::new().unwrap();
 
     hotwatch
         .watch(path.clone(), move |event| {
             if let Event::Write(_) = event {
-                let config = read(&path);
-                sender.send(config).unwrap();
+                sender.send(try_read(&path)).unwrap();
             }
         })
         .unwrap();
 
     (receiver, hotwatch)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reads_config_from_before_any_additions() {
+        let config: Config =
+            toml::from_str(include_str!("../fixtures/baseline_boids.toml")).unwrap();
+        let shipped: Config = toml::from_str(include_str!("../boids.toml")).unwrap();
+
+        assert_eq!(config.population, 20);
+        assert_eq!(config.separation_kernel, Kernel::Linear);
+        assert_eq!(config.rule, shipped.rule);
+        assert_eq!(config.trail_length, shipped.trail_length);
+        assert_eq!(config.spawn_centre, shipped.spawn_centre);
+        assert_eq!(config.keys.pause, shipped.keys.pause);
+        assert!(!config.check_invariants);
+    }
+}
@@ -1,34 +1,61 @@
+use crate::boids::World;
+use crate::geometry;
 use crate::geometry::Model;
+use crate::physics::Entity;
+use crate::utilities::normalize_or_zero;
 use gl::Surface;
 use glium as gl;
 use nalgebra_glm as na;
+use std::collections::HashMap;
+use std::mem;
 
 #[derive(Copy, Clone)]
 pub struct Vertex {
     pub position: [f32; 3],
+    pub colour: [f32; 4],
 }
 
-gl::implement_vertex!(Vertex, position);
+gl::implement_vertex!(Vertex, position, colour);
 
-pub fn vec3_to_vertex(v: &na::Vec3) -> Vertex {
+pub fn vec3_to_vertex(v: &na::Vec3, colour: &na::Vec4) -> Vertex {
     Vertex {
         position: v.xyz().into(),
+        colour: (*colour).into(),
     }
 }
 
-pub fn solid_color_shader(r: f32, g: f32, b: f32, a: f32) -> String {
-    format!(
-        r#"
-        #version 140
+/// Where and how to draw one copy of an instanced mesh
+#[derive(Copy, Clone)]
+pub struct Instance {
+    pub instance_position: [f32; 2],
+    /// Unit vector that the mesh's y axis is turned to
+    pub instance_heading: [f32; 2],
+    pub instance_scale: f32,
+    pub instance_colour: [f32; 4],
+}
 
-        out vec4 color;
+gl::implement_vertex!(
+    Instance,
+    instance_position,
+    instance_heading,
+    instance_scale,
+    instance_colour
+);
+
+/// A boid facing along `rot`, or up if it has none
+pub fn instance(entity: &Entity, scale: f32, colour: &na::Vec4) -> Instance {
+    let heading = normalize_or_zero(&entity.rot);
 
-        void main() {{
-            color = vec4({}, {}, {}, {});
-        }}
-    "#,
-        r, g, b, a
-    )
+    Instance {
+        instance_position: entity.pos.into(),
+        instance_heading: if heading == na::zero() {
+            [0.0, 1.0]
+        } else {
+            heading.into()
+        },
+        instance_scale: scale,
+        instance_colour: (*colour).into(),
+    }
 }
 
 pub fn simple_program(
@@ -37,86 +64,258 @@ pub fn simple_program(
     let vertex_shader_src = r#"
         #version 140
 
+        uniform mat4 view_projection;
+
         in vec3 position;
+        in vec4 colour;
+        out vec4 vertex_colour;
+
+        void main() {
+        vertex_colour = colour;
+        gl_Position = view_projection * vec4(position.xy, 0.0, 1.0);
+        }
+    "#;
+
+    let fragment_shader_src = r#"
+        #version 140
+
+        in vec4 vertex_colour;
+        out vec4 color;
 
         void main() {
-        gl_Position = vec4(position, 1.0);
+            color = vertex_colour;
         }
     "#;
 
-    gl::Program::from_source(
-        display,
-        vertex_shader_src,
-        &solid_color_shader(0.0, 0.05, 0.2, 1.0),
-        None,
-    )
+    gl::Program::from_source(display, vertex_shader_src, fragment_shader_src, None)
 }
 
-pub struct Drawer {
-    pub display: gl::Display,
-    pub program: gl::Program,
-    vertices: Vec<Vec<Vertex>>,
-    types: Vec<gl::index::PrimitiveType>,
+/// Scales, turns and moves each copy of the mesh on the GPU, colouring it per instance
+pub fn instanced_program(
+    display: &gl::backend::glutin::Display,
+) -> Result<gl::Program, gl::ProgramCreationError> {
+    let vertex_shader_src = r#"
+        #version 140
+
+        uniform mat4 view_projection;
+
+        in vec3 position;
+        in vec2 instance_position;
+        in vec2 instance_heading;
+        in float instance_scale;
+        in vec4 instance_colour;
+        out vec4 vertex_colour;
+
+        void main() {
+            vec2 side = vec2(instance_heading.y, -instance_heading.x);
+            vec2 turned = position.x * side + position.y * instance_heading;
+            vertex_colour = instance_colour;
+            vec2 world_position = instance_position + instance_scale * turned;
+            gl_Position = view_projection * vec4(world_position, 0.0, 1.0);
+        }
+    "#;
+
+    let fragment_shader_src = r#"
+        #version 140
+
+        in vec4 vertex_colour;
+        out vec4 color;
+
+        void main() {
+            color = vertex_colour;
+        }
+    "#;
+
+    gl::Program::from_source(display, vertex_shader_src, fragment_shader_src, None)
 }
 
-pub fn drawer(display: gl::Display, program: gl::Program) -> Drawer {
-    Drawer {
-        display: display,
-        program: program,
-        vertices: Vec::new(),
-        types: Vec::new(),
+/// `buffer`, replaced with a bigger one if it can't hold `count` vertices. Grows to powers of two
+/// so that a slowly growing population doesn't reallocate every frame.
+fn reserve<T: gl::Vertex + Copy>(
+    display: &gl::Display,
+    buffer: &mut gl::VertexBuffer<T>,
+    count: usize,
+) {
+    if count > buffer.len() {
+        *buffer = gl::VertexBuffer::empty_dynamic(display, count.next_power_of_two()).unwrap();
     }
 }
 
-impl Drawer {
-    pub fn add_model(&mut self, Model(verts, kind): Model) {
-        let indices_types = self.types.iter().enumerate();
-        let vertices = &mut self.vertices;
-
-        let mut index = None;
-        indices_types.for_each(|(i, prim_type)| {
-            if *prim_type == kind {
-                index = Some(i);
-            }
-        });
+/// A mesh uploaded once, drawn once per instance added since the last draw.
+/// Instances are streamed into a buffer that is only reallocated when it has to grow.
+struct InstancedMesh {
+    mesh: gl::VertexBuffer<Vertex>,
+    primitive_type: gl::index::PrimitiveType,
+    instances: Vec<Instance>,
+    instance_buffer: gl::VertexBuffer<Instance>,
+}
 
-        let converted = verts.iter().map(vec3_to_vertex);
+impl InstancedMesh {
+    fn new(display: &gl::Display, Model(verts, kind, colour): Model) -> InstancedMesh {
+        let verts: Vec<Vertex> = verts.iter().map(|v| vec3_to_vertex(v, &colour)).collect();
 
-        if let Some(i) = index {
-            vertices[i].extend(converted);
-        } else {
-            vertices.push(converted.collect());
-            self.types.push(kind);
+        InstancedMesh {
+            mesh: gl::VertexBuffer::new(display, &verts).unwrap(),
+            primitive_type: kind,
+            instances: Vec::new(),
+            instance_buffer: gl::VertexBuffer::empty_dynamic(display, 1024).unwrap(),
         }
     }
 
-    pub fn draw(&mut self, frame: &mut gl::Frame) {
-        let types_vertices = self.types.iter().zip(self.vertices.iter());
+    fn draw(
+        &mut self,
+        display: &gl::Display,
+        frame: &mut gl::Frame,
+        program: &gl::Program,
+        view_projection: [[f32; 4]; 4],
+    ) {
+        let count = self.instances.len();
+        if count == 0 {
+            return;
+        }
 
-        types_vertices.for_each(|(prim_type, verts)| {
-            self.draw_vertices(frame, verts, *prim_type);
-        });
+        reserve(display, &mut self.instance_buffer, count);
+        let instances = self.instance_buffer.slice(0..count).unwrap();
+        instances.write(&self.instances);
 
-        self.vertices.clear();
-        self.types.clear();
+        frame
+            .draw(
+                (&self.mesh, instances.per_instance().unwrap()),
+                gl::index::NoIndices(self.primitive_type),
+                program,
+                &gl::uniform! { view_projection: view_projection },
+                &Default::default(),
+            )
+            .unwrap();
+
+        self.instances.clear();
     }
+}
+
+/// Every model of one primitive type added since the last draw, streamed into a buffer that is
+/// kept from frame to frame and only reallocated when it has to grow
+struct Batch {
+    primitive_type: gl::index::PrimitiveType,
+    vertices: Vec<Vertex>,
+    buffer: gl::VertexBuffer<Vertex>,
+}
 
-    pub fn draw_vertices(
-        &self,
+impl Batch {
+    fn draw(
+        &mut self,
+        display: &gl::Display,
         frame: &mut gl::Frame,
-        verts: &[Vertex],
-        primitive_type: gl::index::PrimitiveType,
+        program: &gl::Program,
+        view_projection: [[f32; 4]; 4],
     ) {
-        let vertex_buffer = gl::vertex::VertexBuffer::new(&self.display, verts).unwrap();
+        let count = self.vertices.len();
+        if count == 0 {
+            return;
+        }
+
+        reserve(display, &mut self.buffer, count);
+        let vertices = self.buffer.slice(0..count).unwrap();
+        vertices.write(&self.vertices);
 
         frame
             .draw(
-                &vertex_buffer,
-                &gl::index::NoIndices(primitive_type),
-                &self.program,
-                &gl::uniform! {},
+                vertices,
+                gl::index::NoIndices(self.primitive_type),
+                program,
+                &gl::uniform! { view_projection: view_projection },
                 &Default::default(),
             )
             .unwrap();
+
+        self.vertices.clear();
+    }
+}
+
+/// `PrimitiveType` can't be hashed itself. Patches of different sizes need batches of their own.
+type BatchKey = (mem::Discriminant<gl::index::PrimitiveType>, u16);
+
+fn batch_key(primitive_type: gl::index::PrimitiveType) -> BatchKey {
+    let vertices_per_patch = match primitive_type {
+        gl::index::PrimitiveType::Patches { vertices_per_patch } => vertices_per_patch,
+        _ => 0,
+    };
+
+    (mem::discriminant(&primitive_type), vertices_per_patch)
+}
+
+pub struct Drawer {
+    pub display: gl::Display,
+    pub program: gl::Program,
+    instanced_program: gl::Program,
+    /// In the order each primitive type was first added, which is the order they're drawn in
+    batches: Vec<Batch>,
+    batch_indices: HashMap<BatchKey, usize>,
+    boids: InstancedMesh,
+}
+
+pub fn drawer(display: gl::Display, program: gl::Program) -> Drawer {
+    let instanced_program = instanced_program(&display).unwrap();
+    let boids = InstancedMesh::new(&display, geometry::boid());
+
+    Drawer {
+        display: display,
+        program: program,
+        instanced_program,
+        batches: Vec::new(),
+        batch_indices: HashMap::new(),
+        boids,
+    }
+}
+
+impl Drawer {
+    pub fn add_model(&mut self, Model(verts, kind, colour): Model) {
+        self.add_vertices(
+            kind,
+            verts.iter().map(|v| vec3_to_vertex(v, &colour)).collect(),
+        );
+    }
+
+    /// Like `add_model`, but with a colour per vertex
+    pub fn add_vertices(&mut self, kind: gl::index::PrimitiveType, vertices: Vec<Vertex>) {
+        let batches = &mut self.batches;
+        let display = &self.display;
+        let index = *self
+            .batch_indices
+            .entry(batch_key(kind))
+            .or_insert_with(|| {
+                batches.push(Batch {
+                    primitive_type: kind,
+                    vertices: Vec::new(),
+                    buffer: gl::VertexBuffer::empty_dynamic(display, 1024).unwrap(),
+                });
+                batches.len() - 1
+            });
+
+        batches[index].vertices.extend(vertices);
+    }
+
+    /// One boid per entity, `colours` holding one colour per entity
+    pub fn add_boids(&mut self, World(entities): &World, colours: &[na::Vec4]) {
+        let instances = entities
+            .iter()
+            .zip(colours)
+            .map(|(entity, colour)| instance(entity, geometry::BOID_SCALE, colour));
+        self.boids.instances.extend(instances);
+    }
+
+    /// Draws everything added since the last draw, through the camera's `view_projection`
+    pub fn draw(&mut self, frame: &mut gl::Frame, view_projection: &na::Mat4) {
+        let view_projection: [[f32; 4]; 4] = (*view_projection).into();
+
+        for batch in &mut self.batches {
+            batch.draw(&self.display, frame, &self.program, view_projection);
+        }
+
+        self.boids.draw(
+            &self.display,
+            frame,
+            &self.instanced_program,
+            view_projection,
+        );
     }
 }
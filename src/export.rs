@@ -0,0 +1,222 @@
+//! Per-step boid state and flock metrics in formats that load straight into Python.
+//! Headings are counter-clockwise from the x axis, in radians.
+
+use crate::boids::World;
+use crate::metrics::Metrics;
+use crate::physics::Entity;
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+pub const FIELDS: [&str; 8] = ["id", "x", "y", "vx", "vy", "heading", "angular_vel", "area"];
+
+/// Ids are exact as f32 for the first 2^24 boids
+fn fields(boid: &Entity) -> [f32; 8] {
+    [
+        boid.id as f32,
+        boid.pos.x,
+        boid.pos.y,
+        boid.vel.x,
+        boid.vel.y,
+        boid.rot.y.atan2(boid.rot.x),
+        boid.angular_vel,
+        boid.area,
+    ]
+}
+
+/// One row per boid per step
+pub struct Csv<W: Write> {
+    writer: W,
+}
+
+pub fn create_csv(path: &Path) -> io::Result<Csv<BufWriter<File>>> {
+    Csv::new(BufWriter::new(File::create(path)?))
+}
+
+impl<W: Write> Csv<W> {
+    pub fn new(mut writer: W) -> io::Result<Csv<W>> {
+        writeln!(writer, "step,{}", FIELDS.join(","))?;
+        Ok(Csv { writer })
+    }
+
+    pub fn write(&mut self, step: u64, World(boids): &World) -> io::Result<()> {
+        for boid in boids {
+            let values: Vec<String> = fields(boid).iter().map(f32::to_string).collect();
+            writeln!(self.writer, "{},{}", step, values.join(","))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// One row per step
+pub struct MetricsCsv<W: Write> {
+    writer: W,
+}
+
+pub fn create_metrics_csv(path: &Path) -> io::Result<MetricsCsv<BufWriter<File>>> {
+    MetricsCsv::new(BufWriter::new(File::create(path)?))
+}
+
+impl<W: Write> MetricsCsv<W> {
+    pub fn new(mut writer: W) -> io::Result<MetricsCsv<W>> {
+        writeln!(
+            writer,
+            "step,polarisation,milling,mean_nearest_neighbour_distance,radius_of_gyration,mean_speed,speed_std_dev,min_speed,max_speed"
+        )?;
+        Ok(MetricsCsv { writer })
+    }
+
+    pub fn write(&mut self, step: u64, metrics: &Metrics) -> io::Result<()> {
+        writeln!(
+            self.writer,
+            "{},{},{},{},{},{},{},{},{}",
+            step,
+            metrics.polarisation,
+            metrics.milling,
+            metrics.mean_nearest_neighbour_distance,
+            metrics.radius_of_gyration,
+            metrics.mean_speed,
+            metrics.speed_std_dev,
+            metrics.min_speed,
+            metrics.max_speed
+        )
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// A little endian f32 array of shape (steps, boids, `FIELDS.len()`).
+/// Steps are streamed to disk, and the shape in the header is filled in by `finish`.
+pub struct Npy<W: Write + Seek> {
+    writer: W,
+    steps: usize,
+    boids: Option<usize>,
+}
+
+/// Room for the largest shape we could write, so the header can be rewritten in place
+const NPY_HEADER_LENGTH: usize = 128;
+
+pub fn create_npy(path: &Path) -> io::Result<Npy<BufWriter<File>>> {
+    Npy::new(BufWriter::new(File::create(path)?))
+}
+
+impl<W: Write + Seek> Npy<W> {
+    pub fn new(mut writer: W) -> io::Result<Npy<W>> {
+        writer.write_all(&npy_header(0, 0))?;
+        Ok(Npy {
+            writer,
+            steps: 0,
+            boids: None,
+        })
+    }
+
+    /// Every step must have the same number of boids
+    pub fn write(&mut self, World(boids): &World) -> io::Result<()> {
+        if *self.boids.get_or_insert(boids.len()) != boids.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the population changed, but .npy arrays need the same number of boids every step",
+            ));
+        }
+
+        for boid in boids {
+            for value in &fields(boid) {
+                self.writer.write_all(&value.to_le_bytes())?;
+            }
+        }
+        self.steps += 1;
+
+        Ok(())
+    }
+
+    pub fn finish(&mut self) -> io::Result<()> {
+        self.writer.seek(SeekFrom::Start(0))?;
+        self.writer
+            .write_all(&npy_header(self.steps, self.boids.unwrap_or(0)))?;
+        self.writer.seek(SeekFrom::End(0))?;
+        self.writer.flush()
+    }
+}
+
+fn npy_header(steps: usize, boids: usize) -> Vec<u8> {
+    let mut dictionary = format!(
+        "{{'descr': '<f4', 'fortran_order': False, 'shape': ({}, {}, {}), }}",
+        steps,
+        boids,
+        FIELDS.len()
+    );
+    // magic, version, header length, dictionary, then padding ending in a newline
+    let padding = NPY_HEADER_LENGTH - 10 - dictionary.len();
+    dictionary.push_str(&" ".repeat(padding - 1));
+    dictionary.push('\n');
+
+    let mut header = b"\x93NUMPY\x01\x00".to_vec();
+    header.extend_from_slice(&(dictionary.len() as u16).to_le_bytes());
+    header.extend_from_slice(dictionary.as_bytes());
+    header
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm as na;
+    use std::io::Cursor;
+
+    fn world() -> World {
+        World(vec![
+            Entity {
+                id: 4,
+                pos: na::vec2(0.5, -0.25),
+                vel: na::vec2(1.0, 0.0),
+                rot: na::vec2(0.0, 1.0),
+                ..Default::default()
+            },
+            Entity {
+                id: 9,
+                ..Default::default()
+            },
+        ])
+    }
+
+    #[test]
+    fn test_csv() {
+        let mut bytes = Vec::new();
+        let mut csv = Csv::new(&mut bytes).unwrap();
+        csv.write(3, &world()).unwrap();
+
+        let text = String::from_utf8(bytes).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "step,id,x,y,vx,vy,heading,angular_vel,area");
+        assert!(lines[1].starts_with("3,4,0.5,-0.25,1,0,1.57"));
+        assert_eq!(lines[2], "3,9,0,0,0,0,0,0,0.25");
+    }
+
+    #[test]
+    fn test_npy() {
+        let mut npy = Npy::new(Cursor::new(Vec::new())).unwrap();
+        for _ in 0..3 {
+            npy.write(&world()).unwrap();
+        }
+        npy.finish().unwrap();
+        assert!(npy.write(&World(vec![])).is_err());
+
+        let bytes = npy.writer.into_inner();
+        assert_eq!(&bytes[..6], b"\x93NUMPY");
+        let header = String::from_utf8_lossy(&bytes[10..NPY_HEADER_LENGTH]);
+        assert!(header.contains("'shape': (3, 2, 8)"));
+        assert!(header.ends_with('\n'));
+        assert_eq!(bytes.len(), NPY_HEADER_LENGTH + 3 * 2 * 8 * 4);
+        assert_eq!(
+            &bytes[NPY_HEADER_LENGTH..NPY_HEADER_LENGTH + 8],
+            &[4.0f32.to_le_bytes(), 0.5f32.to_le_bytes()].concat()[..]
+        );
+    }
+}
@@ -1,26 +1,346 @@
 use crate::boids::World;
+use crate::config;
 use crate::physics::Entity;
 use nalgebra_glm as na;
 use rand::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::f32::consts::PI;
 
-pub fn random_world(population: u32, min_pos: na::Vec2, max_pos: na::Vec2) -> World {
-    let mut rng = thread_rng();
-    let mut boids = Vec::new();
+/// The simulation's only source of randomness, so that a run is reproducible from its seed
+pub type SimulationRng = rand_pcg::Pcg32;
 
-    for _ in 0..population {
-        let pos = na::vec2(
-            rng.gen_range(min_pos.x, max_pos.x),
-            rng.gen_range(min_pos.y, max_pos.y),
+pub fn seeded_rng(seed: u64) -> SimulationRng {
+    SimulationRng::seed_from_u64(seed)
+}
+
+/// How the initial population is spread over the arena
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Spawn {
+    Uniform,
+    /// No two boids closer than `spawn_spacing`
+    PoissonDisk,
+    /// One boid per grid cell, offset by up to `spawn_jitter` of a cell
+    JitteredGrid,
+    /// Normally distributed around `spawn_centre` with standard deviation `spawn_spread`
+    Gaussian,
+}
+
+/// The initial world for `config.spawn`, filling the arena from (-1, -1) to (1, 1).
+/// Poisson-disk spawning can run out of room before placing the whole population, in which case
+/// the world is smaller and the shortfall is printed.
+pub fn spawn_world(config: &config::Config, rng: &mut impl Rng) -> World {
+    let (min_pos, max_pos) = (na::vec2(-1.0, -1.0), na::vec2(1.0, 1.0));
+    let population = config.population as usize;
+
+    let positions = match config.spawn {
+        Spawn::Uniform => return random_world(config.population, min_pos, max_pos, rng),
+        Spawn::PoissonDisk => {
+            let positions =
+                poisson_disk_positions(population, min_pos, max_pos, config.spawn_spacing, rng);
+            if let Err(shortfall) = check_population(positions.len(), config) {
+                eprintln!("{}", shortfall);
+            }
+            positions
+        }
+        Spawn::JitteredGrid => {
+            jittered_grid_positions(population, min_pos, max_pos, config.spawn_jitter, rng)
+        }
+        Spawn::Gaussian => gaussian_positions(
+            population,
+            config.spawn_centre,
+            config.spawn_spread,
+            min_pos,
+            max_pos,
+            rng,
+        ),
+    };
+
+    world_at_positions(&positions, rng)
+}
+
+/// Whether `placed` boids is the whole of the configured population
+fn check_population(placed: usize, config: &config::Config) -> Result<(), String> {
+    if placed < config.population as usize {
+        Err(format!(
+            "only {} of {} boids fit {} apart, so the run starts with {}",
+            placed, config.population, config.spawn_spacing, placed
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+pub fn random_world(
+    population: u32,
+    min_pos: na::Vec2,
+    max_pos: na::Vec2,
+    rng: &mut impl Rng,
+) -> World {
+    World(
+        (0..population)
+            .map(|_| random_entity(min_pos, max_pos, rng))
+            .collect(),
+    )
+}
+
+/// A boid anywhere in the area, with a random heading
+pub fn random_entity(min_pos: na::Vec2, max_pos: na::Vec2, rng: &mut impl Rng) -> Entity {
+    let pos = na::vec2(
+        rng.gen_range(min_pos.x, max_pos.x),
+        rng.gen_range(min_pos.y, max_pos.y),
+    );
+    let rot = na::rotate_vec2(&na::vec2(1.0, 0.0), rng.gen_range(0.0, 2.0 * PI));
+    let wander_angle = rng.gen_range(-PI, PI);
+
+    Entity {
+        pos,
+        rot,
+        wander_angle,
+        ..Default::default()
+    }
+}
+
+/// Boids at the given positions with random headings
+pub fn world_at_positions(positions: &[na::Vec2], rng: &mut impl Rng) -> World {
+    World(
+        positions
+            .iter()
+            .map(|&pos| Entity {
+                pos,
+                rot: na::rotate_vec2(&na::vec2(1.0, 0.0), rng.gen_range(0.0, 2.0 * PI)),
+                wander_angle: rng.gen_range(-PI, PI),
+                ..Default::default()
+            })
+            .collect(),
+    )
+}
+
+/// Bridson's algorithm. Returns fewer than `count` positions if the area fills up first.
+pub fn poisson_disk_positions(
+    count: usize,
+    min_pos: na::Vec2,
+    max_pos: na::Vec2,
+    spacing: f32,
+    rng: &mut impl Rng,
+) -> Vec<na::Vec2> {
+    const ATTEMPTS: u32 = 30;
+
+    let size = max_pos - min_pos;
+    if count == 0 || spacing <= 0.0 || size.x <= 0.0 || size.y <= 0.0 {
+        return Vec::new();
+    }
+
+    // Cells small enough to hold at most one position each
+    let cell_size = spacing / 2.0f32.sqrt();
+    let columns = (size.x / cell_size).ceil() as usize;
+    let rows = (size.y / cell_size).ceil() as usize;
+    let mut grid: Vec<Option<usize>> = vec![None; columns * rows];
+    let cell = |pos: &na::Vec2| {
+        let column = (((pos.x - min_pos.x) / cell_size) as usize).min(columns - 1);
+        let row = (((pos.y - min_pos.y) / cell_size) as usize).min(rows - 1);
+        (column, row)
+    };
+
+    let first = na::vec2(
+        rng.gen_range(min_pos.x, max_pos.x),
+        rng.gen_range(min_pos.y, max_pos.y),
+    );
+    let (column, row) = cell(&first);
+    grid[row * columns + column] = Some(0);
+    let mut positions = vec![first];
+    let mut active = vec![0];
+
+    while !active.is_empty() && positions.len() < count {
+        let active_index = rng.gen_range(0, active.len());
+        let centre = positions[active[active_index]];
+
+        let candidate = (0..ATTEMPTS)
+            .map(|_| {
+                let angle = rng.gen_range(0.0, 2.0 * PI);
+                let distance = rng.gen_range(spacing, 2.0 * spacing);
+                centre + distance * na::vec2(angle.cos(), angle.sin())
+            })
+            .find(|candidate| {
+                if candidate.x < min_pos.x
+                    || candidate.x >= max_pos.x
+                    || candidate.y < min_pos.y
+                    || candidate.y >= max_pos.y
+                {
+                    return false;
+                }
+
+                let (column, row) = cell(candidate);
+                let neighbouring_rows = row.saturating_sub(2)..(row + 3).min(rows);
+                neighbouring_rows.into_iter().all(|r| {
+                    (column.saturating_sub(2)..(column + 3).min(columns)).all(|c| {
+                        grid[r * columns + c]
+                            .is_none_or(|i| na::distance(&positions[i], candidate) >= spacing)
+                    })
+                })
+            });
+
+        match candidate {
+            Some(candidate) => {
+                let (column, row) = cell(&candidate);
+                grid[row * columns + column] = Some(positions.len());
+                active.push(positions.len());
+                positions.push(candidate);
+            }
+            None => {
+                active.swap_remove(active_index);
+            }
+        }
+    }
+
+    positions
+}
+
+/// The smallest grid with roughly square cells that fits `count` positions, filled row by row
+pub fn jittered_grid_positions(
+    count: usize,
+    min_pos: na::Vec2,
+    max_pos: na::Vec2,
+    jitter: f32,
+    rng: &mut impl Rng,
+) -> Vec<na::Vec2> {
+    if count == 0 {
+        return Vec::new();
+    }
+
+    let size = max_pos - min_pos;
+    let columns = ((count as f32 * size.x / size.y).sqrt().ceil() as usize).max(1);
+    let rows = count.div_ceil(columns);
+    let cell_size = na::vec2(size.x / columns as f32, size.y / rows as f32);
+    let jitter = jitter.clamp(0.0, 1.0);
+
+    (0..count)
+        .map(|i| {
+            let cell_centre = na::vec2(
+                ((i % columns) as f32 + 0.5) * cell_size.x,
+                ((i / columns) as f32 + 0.5) * cell_size.y,
+            );
+            let offset = na::vec2(
+                rng.gen_range(-0.5, 0.5) * jitter * cell_size.x,
+                rng.gen_range(-0.5, 0.5) * jitter * cell_size.y,
+            );
+            min_pos + cell_centre + offset
+        })
+        .collect()
+}
+
+/// Box-Muller samples, clamped to the area
+pub fn gaussian_positions(
+    count: usize,
+    centre: na::Vec2,
+    spread: f32,
+    min_pos: na::Vec2,
+    max_pos: na::Vec2,
+    rng: &mut impl Rng,
+) -> Vec<na::Vec2> {
+    (0..count)
+        .map(|_| {
+            let radius = (-2.0 * (1.0 - rng.gen::<f32>()).ln()).sqrt() * spread;
+            let angle = rng.gen_range(0.0, 2.0 * PI);
+            let pos = centre + radius * na::vec2(angle.cos(), angle.sin());
+            na::vec2(
+                pos.x.clamp(min_pos.x, max_pos.x),
+                pos.y.clamp(min_pos.y, max_pos.y),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn test_poisson_disk_spacing(seed in 0..1000u64, spacing in 0.05..0.5f32) {
+            let mut rng = seeded_rng(seed);
+            let (min_pos, max_pos) = (na::vec2(-1.0, -1.0), na::vec2(1.0, 1.0));
+            let positions = poisson_disk_positions(100, min_pos, max_pos, spacing, &mut rng);
+
+            assert!(!positions.is_empty() && positions.len() <= 100);
+            for (i, a) in positions.iter().enumerate() {
+                assert!(a.x >= -1.0 && a.x < 1.0 && a.y >= -1.0 && a.y < 1.0);
+                for b in &positions[i + 1..] {
+                    assert!(na::distance(a, b) >= spacing);
+                }
+            }
+        }
+
+        #[test]
+        fn test_jittered_grid_count_and_bounds(seed in 0..1000u64, count in 1..200usize, jitter in 0.0..1.0f32) {
+            let mut rng = seeded_rng(seed);
+            let positions = jittered_grid_positions(count, na::vec2(-1.0, -0.5), na::vec2(1.0, 0.5), jitter, &mut rng);
+
+            assert_eq!(positions.len(), count);
+            for pos in positions {
+                assert!(pos.x >= -1.0 && pos.x <= 1.0 && pos.y >= -0.5 && pos.y <= 0.5);
+            }
+        }
+    }
+
+    #[test]
+    fn test_gaussian_blob_centre() {
+        let mut rng = seeded_rng(3);
+        let centre = na::vec2(0.25, -0.25);
+        let positions = gaussian_positions(
+            2000,
+            centre,
+            0.1,
+            na::vec2(-1.0, -1.0),
+            na::vec2(1.0, 1.0),
+            &mut rng,
         );
-        let rot = na::rotate_vec2(&na::vec2(1.0, 0.0), rng.gen_range(0.0, 2.0 * PI));
 
-        boids.push(Entity {
-            pos,
-            rot,
-            ..Default::default()
-        });
+        assert!(na::distance(&crate::utilities::mean(&positions), &centre) < 0.01);
     }
 
-    World(boids)
+    #[test]
+    fn test_poisson_disk_reports_a_population_that_doesnt_fit() {
+        let config = config::Config {
+            spawn: Spawn::PoissonDisk,
+            spawn_spacing: 0.15,
+            population: 500,
+            ..crate::test_utils::test_config()
+        };
+        let world = spawn_world(&config, &mut seeded_rng(config.seed));
+
+        assert!(world.0.len() < 500);
+        assert_eq!(
+            check_population(world.0.len(), &config),
+            Err(format!(
+                "only {0} of 500 boids fit 0.15 apart, so the run starts with {0}",
+                world.0.len()
+            ))
+        );
+        assert!(check_population(500, &config).is_ok());
+    }
+
+    #[test]
+    fn test_spawn_is_reproducible() {
+        for spawn in &[
+            Spawn::Uniform,
+            Spawn::PoissonDisk,
+            Spawn::JitteredGrid,
+            Spawn::Gaussian,
+        ] {
+            let config = config::Config {
+                spawn: *spawn,
+                ..crate::test_utils::test_config()
+            };
+            let a = spawn_world(&config, &mut seeded_rng(config.seed));
+            let b = spawn_world(&config, &mut seeded_rng(config.seed));
+
+            assert_eq!(a.0.len(), config.population as usize);
+            for (a, b) in a.0.iter().zip(&b.0) {
+                assert_eq!(a.pos, b.pos);
+                assert_eq!(a.rot, b.rot);
+            }
+        }
+    }
 }
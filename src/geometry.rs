@@ -1,26 +1,203 @@
 use crate::boids::World;
-use crate::physics::Entity;
+use crate::config;
+use crate::groups;
+use crate::scenario::Environment;
 use glium::index::PrimitiveType;
 use nalgebra_glm as na;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
 
-pub struct Model(pub Vec<na::Vec3>, pub PrimitiveType);
+/// Vertices, how to assemble them, and an RGBA colour
+pub struct Model(pub Vec<na::Vec3>, pub PrimitiveType, pub na::Vec4);
 
-impl Model {
-    pub fn map<F: Fn(&na::Vec3) -> na::Vec3>(&self, f: F) -> Model {
-        Model(self.0.iter().map(f).collect(), self.1)
+/// Half the width of a boid, in world units
+pub const BOID_SCALE: f32 = 0.005;
+
+pub fn default_colour() -> na::Vec4 {
+    na::vec4(0.3, 0.6, 1.0, 1.0)
+}
+
+/// What decides each boid's colour
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ColourBy {
+    /// Every boid in `default_colour`
+    Uniform,
+    Speed,
+    /// Around the colour wheel, whatever the palette, so that opposite headings are far apart
+    Heading,
+    /// Neighbours within the influence radius
+    Density,
+    Species,
+    Group,
+    /// Drag area, which grows as boids turn harder
+    Area,
+}
+
+/// Colour ramps for continuous values. Values are stretched so the lowest in the world is at
+/// the start of the ramp and the highest at the end.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Palette {
+    Viridis,
+    Magma,
+    Greyscale,
+    /// Fully saturated hues, starting and ending at red
+    Rainbow,
+}
+
+impl Palette {
+    /// `t` is clamped to [0, 1]
+    pub fn colour(self, t: f32) -> na::Vec4 {
+        const VIRIDIS: [[f32; 3]; 5] = [
+            [0.267, 0.005, 0.329],
+            [0.229, 0.322, 0.546],
+            [0.128, 0.567, 0.551],
+            [0.369, 0.789, 0.383],
+            [0.993, 0.906, 0.144],
+        ];
+        const MAGMA: [[f32; 3]; 5] = [
+            [0.001, 0.000, 0.014],
+            [0.316, 0.071, 0.485],
+            [0.716, 0.215, 0.475],
+            [0.987, 0.536, 0.382],
+            [0.987, 0.991, 0.750],
+        ];
+        // Starts above black, which is the background
+        const GREYSCALE: [[f32; 3]; 2] = [[0.25, 0.25, 0.25], [1.0, 1.0, 1.0]];
+
+        let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+        match self {
+            Palette::Viridis => ramp(&VIRIDIS, t),
+            Palette::Magma => ramp(&MAGMA, t),
+            Palette::Greyscale => ramp(&GREYSCALE, t),
+            Palette::Rainbow => hue(t),
+        }
     }
 }
 
-pub fn world_to_models<F: Fn(&Entity) -> Model>(
-    World(entities): &World,
-    funcs: &[F],
-) -> Vec<Model> {
-    entities
+/// Linear interpolation between evenly spaced stops
+fn ramp(stops: &[[f32; 3]], t: f32) -> na::Vec4 {
+    let position = t * (stops.len() - 1) as f32;
+    let index = (position as usize).min(stops.len() - 2);
+    let fraction = position - index as f32;
+    let [r0, g0, b0] = stops[index];
+    let [r1, g1, b1] = stops[index + 1];
+
+    na::lerp(
+        &na::vec4(r0, g0, b0, 1.0),
+        &na::vec4(r1, g1, b1, 1.0),
+        fraction,
+    )
+}
+
+/// A fully saturated colour `t` of the way round the colour wheel from red
+fn hue(t: f32) -> na::Vec4 {
+    let channel = |offset: f32| {
+        let k = (offset + t * 6.0) % 6.0;
+        1.0 - k.min(4.0 - k).clamp(0.0, 1.0)
+    };
+
+    na::vec4(channel(5.0), channel(3.0), channel(1.0), 1.0)
+}
+
+/// Stretches `values` over [0, 1], putting them all in the middle if they're equal
+fn normalised(values: Vec<f32>) -> Vec<f32> {
+    let min = values.iter().cloned().fold(f32::INFINITY, f32::min);
+    let max = values.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+
+    values
+        .into_iter()
+        .map(|value| {
+            if max > min {
+                (value - min) / (max - min)
+            } else {
+                0.5
+            }
+        })
+        .collect()
+}
+
+fn neighbour_counts(World(boids): &World, radius: f32) -> Vec<f32> {
+    boids
         .iter()
-        .flat_map(|entity| funcs.iter().map(move |f| f(entity)))
+        .map(|boid| {
+            boids
+                .iter()
+                .filter(|other| na::distance(&boid.pos, &other.pos) <= radius)
+                .count() as f32
+                - 1.0
+        })
         .collect()
 }
 
+/// A distinct colour per group label, cycling after eight, and grey for boids in no group
+pub fn group_colour(label: Option<usize>) -> na::Vec4 {
+    const PALETTE: [[f32; 3]; 8] = [
+        [0.9, 0.3, 0.3],
+        [0.3, 0.8, 0.3],
+        [0.3, 0.5, 1.0],
+        [0.9, 0.8, 0.2],
+        [0.8, 0.3, 0.9],
+        [0.2, 0.8, 0.8],
+        [1.0, 0.6, 0.2],
+        [0.9, 0.9, 0.9],
+    ];
+
+    match label {
+        Some(label) => {
+            let [r, g, b] = PALETTE[label % PALETTE.len()];
+            na::vec4(r, g, b, 1.0)
+        }
+        None => na::vec4(0.4, 0.4, 0.4, 1.0),
+    }
+}
+
+/// One colour per boid, following `config.colour_by` and `config.palette`
+pub fn boid_colours(world: &World, config: &config::Config) -> Vec<na::Vec4> {
+    let World(boids) = world;
+    let values = match config.colour_by {
+        ColourBy::Uniform => return vec![default_colour(); boids.len()],
+        ColourBy::Heading => {
+            return boids
+                .iter()
+                .map(|boid| hue(boid.rot.y.atan2(boid.rot.x) / (2.0 * PI) + 0.5))
+                .collect()
+        }
+        ColourBy::Species => {
+            return boids
+                .iter()
+                .map(|boid| group_colour(Some(boid.species as usize)))
+                .collect()
+        }
+        ColourBy::Group => {
+            let groups = groups::find(world, config);
+            return groups::labels(&groups, boids.len())
+                .into_iter()
+                .map(group_colour)
+                .collect();
+        }
+        ColourBy::Speed => boids.iter().map(|boid| na::magnitude(&boid.vel)).collect(),
+        ColourBy::Density => neighbour_counts(world, config.influence_radius),
+        ColourBy::Area => boids.iter().map(|boid| boid.area).collect(),
+    };
+
+    normalised(values)
+        .into_iter()
+        .map(|t| config.palette.colour(t))
+        .collect()
+}
+
+impl Model {
+    pub fn map<F: Fn(&na::Vec3) -> na::Vec3>(&self, f: F) -> Model {
+        Model(self.0.iter().map(f).collect(), self.1, self.2)
+    }
+
+    pub fn with_colour(self, colour: na::Vec4) -> Model {
+        Model(self.0, self.1, colour)
+    }
+}
+
 pub fn boid() -> Model {
     Model(
         vec![
@@ -32,6 +209,7 @@ pub fn boid() -> Model {
             na::vec3(0.0, 0.0, 1.0),
         ],
         PrimitiveType::TrianglesList,
+        default_colour(),
     )
 }
 
@@ -56,28 +234,118 @@ pub fn arrow_vector(vector: &na::Vec2) -> Model {
         .map(|v| na::rotation2d(angle) * v)
         .collect(),
         PrimitiveType::LinesList,
+        default_colour(),
     )
 }
 
-pub fn position_rotation_to_matrix(pos: &na::Vec2, rot: &na::Vec2) -> na::Mat3 {
-    let mut rotation = na::normalize(&rot.xy());
-    if rot.xy() == na::zero() {
-        rotation = na::vec2(0.0, 1.0)
-    }
+/// A circle outline centred on the origin
+pub fn circle(radius: f32, segments: u32) -> Model {
+    let point = |i: u32| {
+        let angle = 2.0 * std::f32::consts::PI * i as f32 / segments as f32;
+        na::vec3(radius * angle.cos(), radius * angle.sin(), 1.0)
+    };
 
-    let mut angle = na::angle(&na::vec2(0.0, 1.0), &rotation);
-    if rotation.x > 0.0 {
-        angle = -angle
-    }
-    na::translation2d(&pos.xy()) * na::rotation2d(angle)
+    Model(
+        (0..segments)
+            .flat_map(|i| vec![point(i), point(i + 1)])
+            .collect(),
+        PrimitiveType::LinesList,
+        default_colour(),
+    )
+}
+
+/// Obstacle outlines and goal point markers
+pub fn environment_models(environment: &Environment) -> Vec<Model> {
+    let obstacles = environment.obstacles.iter().map(|obstacle| {
+        circle(obstacle.radius, 32)
+            .map(|v| na::translation2d(&obstacle.centre) * v)
+            .with_colour(na::vec4(0.5, 0.5, 0.5, 1.0))
+    });
+    let goals = environment.goals.iter().map(|goal| {
+        circle(0.02, 8)
+            .map(|v| na::translation2d(&goal.position) * v)
+            .with_colour(na::vec4(0.2, 0.8, 0.3, 1.0))
+    });
+
+    obstacles.chain(goals).collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::physics::Entity;
+    use crate::test_utils::test_config;
     use nalgebra_glm::normalize;
     use proptest::prelude::*;
 
+    proptest! {
+        #[test]
+        fn test_palettes_stay_in_gamut(t in -0.5..1.5f32) {
+            for palette in &[Palette::Viridis, Palette::Magma, Palette::Greyscale, Palette::Rainbow] {
+                let colour = palette.colour(t);
+                for channel in colour.iter() {
+                    assert!((0.0..=1.0).contains(channel));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_palette_ends() {
+        assert_eq!(
+            Palette::Greyscale.colour(0.0),
+            na::vec4(0.25, 0.25, 0.25, 1.0)
+        );
+        assert_eq!(Palette::Greyscale.colour(1.0), na::vec4(1.0, 1.0, 1.0, 1.0));
+        assert_eq!(Palette::Rainbow.colour(0.0), na::vec4(1.0, 0.0, 0.0, 1.0));
+        assert_eq!(
+            Palette::Rainbow.colour(1.0 / 3.0),
+            na::vec4(0.0, 1.0, 0.0, 1.0)
+        );
+        assert_eq!(Palette::Rainbow.colour(1.0), Palette::Rainbow.colour(0.0));
+    }
+
+    #[test]
+    fn test_colour_by_density() {
+        let boid = |x: f32| Entity {
+            pos: na::vec2(x, 0.0),
+            ..Default::default()
+        };
+        let world = World(vec![boid(0.0), boid(0.1), boid(0.2), boid(5.0)]);
+        let config = config::Config {
+            colour_by: ColourBy::Density,
+            palette: Palette::Greyscale,
+            influence_radius: 0.15,
+            ..test_config()
+        };
+
+        let colours = boid_colours(&world, &config);
+        assert_eq!(colours[1], Palette::Greyscale.colour(1.0));
+        assert_eq!(colours[3], Palette::Greyscale.colour(0.0));
+    }
+
+    #[test]
+    fn test_every_mode_colours_every_boid() {
+        let config = test_config();
+        let world = crate::simulation::new(&config).world;
+
+        for colour_by in &[
+            ColourBy::Uniform,
+            ColourBy::Speed,
+            ColourBy::Heading,
+            ColourBy::Density,
+            ColourBy::Species,
+            ColourBy::Group,
+            ColourBy::Area,
+        ] {
+            let config = config::Config {
+                colour_by: *colour_by,
+                ..test_config()
+            };
+            assert_eq!(boid_colours(&world, &config).len(), world.0.len());
+        }
+    }
+
     proptest! {
         #[test]
         fn na_angle(x in -1.0..1.0, y in -1.0..1.0) {
@@ -0,0 +1,277 @@
+//! Golden runs: a fixed config is stepped from its seed and every step is reduced to a
+//! fingerprint, which is compared against the fingerprints checked in under `golden/`.
+//!
+//! Each line of a golden file is
+//!
+//! ```text
+//! step checksum centroid_x centroid_y mean_vel_x mean_vel_y polarisation radius_of_gyration
+//! ```
+//!
+//! The checksum covers the bits of every simulated field of every boid, so exact comparison
+//! catches any change at all. Comparing the remaining summary values within a tolerance instead lets
+//! differences in float rounding through while still catching changes in behaviour.
+//!
+//! In the tests, set `BOIDS_BLESS=1` to rewrite the golden files from the current code, and
+//! `BOIDS_GOLDEN_TOLERANCE` to a number to compare within that tolerance. Headless runs print
+//! the same lines with `--checksum` and compare against a file with `--golden`.
+
+use crate::boids::World;
+use crate::metrics;
+use crate::utilities;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// FNV-1a over the bits of every field of every boid but its id, in order
+pub fn checksum(World(boids): &World) -> u64 {
+    boids
+        .iter()
+        .flat_map(|boid| {
+            [
+                boid.pos.x,
+                boid.pos.y,
+                boid.vel.x,
+                boid.vel.y,
+                boid.rot.x,
+                boid.rot.y,
+                boid.angular_vel,
+                boid.mass,
+                boid.area,
+                boid.resultant_force.x,
+                boid.resultant_force.y,
+                boid.wander_angle,
+            ]
+        })
+        .flat_map(|value| value.to_bits().to_le_bytes())
+        .fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+        })
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fingerprint {
+    pub step: u64,
+    pub checksum: u64,
+    /// Centroid, mean velocity, polarisation and radius of gyration
+    pub summary: [f32; 6],
+}
+
+pub fn fingerprint(step: u64, world: &World) -> Fingerprint {
+    let centroid = metrics::centroid(world);
+    let velocities: Vec<_> = world.0.iter().map(|boid| boid.vel).collect();
+    let mean_vel = utilities::mean(&velocities);
+
+    Fingerprint {
+        step,
+        checksum: checksum(world),
+        summary: [
+            centroid.x,
+            centroid.y,
+            mean_vel.x,
+            mean_vel.y,
+            metrics::polarisation(world),
+            metrics::radius_of_gyration(world),
+        ],
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {:016x}", self.step, self.checksum)?;
+        for value in &self.summary {
+            write!(f, " {}", value)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Fingerprint {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Fingerprint, String> {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() != 8 {
+            return Err(format!("expected 8 fields, found {}", fields.len()));
+        }
+
+        let mut summary = [0.0; 6];
+        for (value, field) in summary.iter_mut().zip(&fields[2..]) {
+            *value = field.parse().map_err(|_| format!("bad number {}", field))?;
+        }
+
+        Ok(Fingerprint {
+            step: fields[0]
+                .parse()
+                .map_err(|_| format!("bad step {}", fields[0]))?,
+            checksum: u64::from_str_radix(fields[1], 16)
+                .map_err(|_| format!("bad checksum {}", fields[1]))?,
+            summary,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Comparison {
+    /// Checksums must match bit for bit
+    Exact,
+    /// Summary values must agree to within this, relative to their magnitude when above 1
+    Tolerance(f32),
+}
+
+/// Describes the first step at which `actual` diverges from `expected`
+pub fn compare(
+    expected: &[Fingerprint],
+    actual: &[Fingerprint],
+    comparison: Comparison,
+) -> Result<(), String> {
+    for (expected, actual) in expected.iter().zip(actual) {
+        let matches =
+            match comparison {
+                Comparison::Exact => expected.checksum == actual.checksum,
+                Comparison::Tolerance(tolerance) => expected
+                    .summary
+                    .iter()
+                    .zip(&actual.summary)
+                    .all(|(expected, actual)| {
+                        (expected - actual).abs() <= tolerance * expected.abs().max(1.0)
+                    }),
+            };
+
+        if expected.step != actual.step || !matches {
+            return Err(format!(
+                "diverged at step {}\nexpected {}\nactual   {}",
+                expected.step, expected, actual
+            ));
+        }
+    }
+
+    if expected.len() != actual.len() {
+        return Err(format!(
+            "expected {} steps, ran {}",
+            expected.len(),
+            actual.len()
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn read(path: &Path) -> Result<Vec<Fingerprint>, String> {
+    fs::read_to_string(path)
+        .map_err(|error| format!("couldn't read {}: {}", path.display(), error))?
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            line.parse()
+                .map_err(|error| format!("{}:{}: {}", path.display(), i + 1, error))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config;
+    use crate::simulation;
+    use crate::simulation::Rule;
+    use crate::test_utils::test_config;
+    use std::env;
+    use std::io;
+    use std::path::PathBuf;
+
+    const STEPS: u64 = 300;
+
+    /// Fingerprints of the initial world and of each of `steps` steps after it
+    fn run(config: &config::Config, steps: u64) -> Vec<Fingerprint> {
+        let mut simulation = simulation::new(config);
+        let goal_functions = simulation::goal_functions(config, &simulation.environment);
+
+        let mut fingerprints = vec![fingerprint(simulation.step, &simulation.world)];
+        for _ in 0..steps {
+            simulation.step(config, &goal_functions, simulation::DELTA_TIME);
+            fingerprints.push(fingerprint(simulation.step, &simulation.world));
+        }
+
+        fingerprints
+    }
+
+    fn write(path: &Path, fingerprints: &[Fingerprint]) -> io::Result<()> {
+        let lines: Vec<String> = fingerprints.iter().map(ToString::to_string).collect();
+        fs::write(path, lines.join("\n") + "\n")
+    }
+
+    fn golden_directory() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("golden")
+    }
+
+    fn comparison() -> Comparison {
+        match env::var("BOIDS_GOLDEN_TOLERANCE") {
+            Ok(tolerance) => Comparison::Tolerance(tolerance.parse().unwrap()),
+            Err(_) => Comparison::Exact,
+        }
+    }
+
+    fn check_golden_run(name: &str, rule: Rule) {
+        let config = config::Config {
+            rule,
+            ..test_config()
+        };
+        let actual = run(&config, STEPS);
+        let path = golden_directory().join(format!("{}.txt", name));
+
+        if env::var_os("BOIDS_BLESS").is_some() {
+            write(&path, &actual).unwrap();
+            return;
+        }
+
+        let expected = read(&path).unwrap();
+        if let Err(divergence) = compare(&expected, &actual, comparison()) {
+            panic!(
+                "{} no longer matches its golden run, {}\nrerun with BOIDS_BLESS=1 if the change is intended",
+                name, divergence
+            );
+        }
+    }
+
+    #[test]
+    fn test_golden_boids() {
+        check_golden_run("boids", Rule::Boids);
+    }
+
+    #[test]
+    fn test_golden_vicsek() {
+        check_golden_run("vicsek", Rule::Vicsek);
+    }
+
+    #[test]
+    fn test_golden_cucker_smale() {
+        check_golden_run("cucker_smale", Rule::CuckerSmale);
+    }
+
+    #[test]
+    fn test_fingerprint_round_trip() {
+        let config = test_config();
+        for fingerprint in run(&config, 10) {
+            assert_eq!(fingerprint.to_string().parse(), Ok(fingerprint));
+        }
+    }
+
+    #[test]
+    fn test_compare() {
+        let expected = run(&test_config(), 5);
+        let mut actual = expected.clone();
+        assert!(compare(&expected, &actual, Comparison::Exact).is_ok());
+
+        actual[3].checksum ^= 1;
+        actual[3].summary[0] += 1e-6;
+        assert!(compare(&expected, &actual, Comparison::Exact).is_err());
+        assert!(compare(&expected, &actual, Comparison::Tolerance(1e-4)).is_ok());
+
+        actual[3].summary[0] += 1.0;
+        assert!(compare(&expected, &actual, Comparison::Tolerance(1e-4)).is_err());
+        assert!(compare(&expected, &actual[..4], Comparison::Exact).is_err());
+    }
+}
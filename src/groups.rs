@@ -0,0 +1,249 @@
+use crate::boids::goals::InfluenceRadius;
+use crate::boids::World;
+use crate::config;
+use crate::utilities::{clockwise_angle_from_j_hat, mean, normalize_or_zero};
+use nalgebra_glm as na;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt;
+
+/// How the world is split into groups
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Grouping {
+    /// Connected components of the graph linking boids within the influence radius
+    Components,
+    /// DBSCAN, where boids are only neighbours if their headings are also similar.
+    /// Boids in sparse regions belong to no group.
+    Dbscan,
+}
+
+pub struct Group {
+    /// Indices into the world
+    pub members: Vec<usize>,
+    pub centroid: na::Vec2,
+    /// Mean of the members' normalised headings, so its magnitude is the group's polarisation
+    pub mean_heading: na::Vec2,
+}
+
+impl Group {
+    fn new(World(boids): &World, members: Vec<usize>) -> Group {
+        let positions: Vec<na::Vec2> = members.iter().map(|&i| boids[i].pos).collect();
+        let headings: Vec<na::Vec2> = members
+            .iter()
+            .map(|&i| boids[i].rot)
+            .map(|rot| normalize_or_zero(&rot))
+            .collect();
+
+        Group {
+            members,
+            centroid: mean(&positions),
+            mean_heading: mean(&headings),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.members.len()
+    }
+}
+
+impl fmt::Display for Group {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} at ({:.3}, {:.3}) heading {:.0}°",
+            self.size(),
+            self.centroid.x,
+            self.centroid.y,
+            clockwise_angle_from_j_hat(&self.mean_heading).to_degrees()
+        )
+    }
+}
+
+/// Groups ordered largest first
+pub fn find(world: &World, config: &config::Config) -> Vec<Group> {
+    let radius = InfluenceRadius(config.influence_radius);
+
+    match config.grouping {
+        Grouping::Components => connected_components(world, radius),
+        Grouping::Dbscan => dbscan(
+            world,
+            radius,
+            config.dbscan_min_points,
+            config.dbscan_max_heading_angle,
+        ),
+    }
+}
+
+/// The group index of every boid in the world, if it belongs to one
+pub fn labels(groups: &[Group], population: usize) -> Vec<Option<usize>> {
+    let mut labels = vec![None; population];
+    for (label, group) in groups.iter().enumerate() {
+        for &member in &group.members {
+            labels[member] = Some(label);
+        }
+    }
+
+    labels
+}
+
+pub fn connected_components(world: &World, InfluenceRadius(radius): InfluenceRadius) -> Vec<Group> {
+    let boids = &world.0;
+    let mut parents: Vec<usize> = (0..boids.len()).collect();
+
+    fn root(parents: &mut [usize], mut i: usize) -> usize {
+        while parents[i] != i {
+            parents[i] = parents[parents[i]];
+            i = parents[i];
+        }
+        i
+    }
+
+    for i in 0..boids.len() {
+        for j in i + 1..boids.len() {
+            if na::distance(&boids[i].pos, &boids[j].pos) <= radius {
+                let (root_i, root_j) = (root(&mut parents, i), root(&mut parents, j));
+                parents[root_i] = root_j;
+            }
+        }
+    }
+
+    let mut members_by_root: Vec<Vec<usize>> = vec![Vec::new(); boids.len()];
+    for i in 0..boids.len() {
+        let root_i = root(&mut parents, i);
+        members_by_root[root_i].push(i);
+    }
+
+    sorted_groups(
+        world,
+        members_by_root
+            .into_iter()
+            .filter(|members| !members.is_empty())
+            .collect(),
+    )
+}
+
+pub fn dbscan(
+    world: &World,
+    InfluenceRadius(radius): InfluenceRadius,
+    min_points: usize,
+    max_heading_angle: f32,
+) -> Vec<Group> {
+    let boids = &world.0;
+    let neighbours = |i: usize| -> Vec<usize> {
+        (0..boids.len())
+            .filter(|&j| {
+                na::distance(&boids[i].pos, &boids[j].pos) <= radius
+                    && (i == j || na::angle(&boids[i].rot, &boids[j].rot) <= max_heading_angle)
+            })
+            .collect()
+    };
+
+    let mut visited = vec![false; boids.len()];
+    let mut assigned = vec![false; boids.len()];
+    let mut clusters = Vec::new();
+
+    for i in 0..boids.len() {
+        if visited[i] {
+            continue;
+        }
+        visited[i] = true;
+
+        let seeds = neighbours(i);
+        if seeds.len() < min_points {
+            continue;
+        }
+
+        let mut members = vec![i];
+        assigned[i] = true;
+        let mut queue: VecDeque<usize> = seeds.into_iter().collect();
+        while let Some(j) = queue.pop_front() {
+            if !visited[j] {
+                visited[j] = true;
+                let expansion = neighbours(j);
+                if expansion.len() >= min_points {
+                    queue.extend(expansion);
+                }
+            }
+            if !assigned[j] {
+                assigned[j] = true;
+                members.push(j);
+            }
+        }
+
+        clusters.push(members);
+    }
+
+    sorted_groups(world, clusters)
+}
+
+fn sorted_groups(world: &World, clusters: Vec<Vec<usize>>) -> Vec<Group> {
+    let mut groups: Vec<Group> = clusters
+        .into_iter()
+        .map(|members| Group::new(world, members))
+        .collect();
+    groups.sort_by_key(|group| std::cmp::Reverse(group.size()));
+
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::Entity;
+
+    fn boid(x: f32, y: f32, heading: na::Vec2) -> Entity {
+        Entity {
+            pos: na::vec2(x, y),
+            rot: heading,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_separate_flocks_are_separate_components() {
+        let up = na::vec2(0.0, 1.0);
+        let world = World(vec![
+            boid(-0.9, -0.9, up),
+            boid(-0.8, -0.9, up),
+            boid(0.9, 0.9, up),
+            boid(-0.7, -0.9, up),
+            boid(0.8, 0.9, up),
+        ]);
+
+        let groups = connected_components(&world, InfluenceRadius(0.15));
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].size(), 3);
+        assert_eq!(groups[1].size(), 2);
+        assert!(na::distance(&groups[1].centroid, &na::vec2(0.85, 0.9)) < 1e-5);
+        assert_eq!(
+            labels(&groups, 5),
+            vec![Some(0), Some(0), Some(1), Some(0), Some(1)]
+        );
+    }
+
+    #[test]
+    fn test_dbscan_splits_by_heading() {
+        let (up, down) = (na::vec2(0.0, 1.0), na::vec2(0.0, -1.0));
+        let world = World(vec![
+            boid(0.0, 0.0, up),
+            boid(0.01, 0.0, up),
+            boid(0.02, 0.0, up),
+            boid(0.0, 0.01, down),
+            boid(0.01, 0.01, down),
+            boid(0.02, 0.01, down),
+            boid(0.9, 0.9, down),
+        ]);
+
+        let groups = dbscan(&world, InfluenceRadius(0.1), 3, 0.5);
+
+        assert_eq!(groups.len(), 2);
+        assert!(groups.iter().all(|group| group.size() == 3));
+        assert!(groups
+            .iter()
+            .all(|group| (na::magnitude(&group.mean_heading) - 1.0).abs() < 1e-5));
+        assert_eq!(labels(&groups, 7)[6], None);
+        assert_eq!(connected_components(&world, InfluenceRadius(0.1)).len(), 2);
+    }
+}
@@ -0,0 +1,178 @@
+use crate::boids::vicsek;
+use crate::boids::World;
+use crate::cli;
+use crate::config;
+use crate::export;
+use crate::golden;
+use crate::groups;
+use crate::metrics;
+use crate::recording;
+use crate::simulation;
+use std::fs::File;
+use std::io;
+use std::io::BufWriter;
+
+/// Steps the simulation as fast as possible, without opening a window
+pub fn run(config: &config::Config, mut simulation: simulation::Simulation, args: &cli::Args) {
+    if args.vicsek_sweep {
+        sweep(config, args.steps);
+        return;
+    }
+
+    let goal_functions = simulation::goal_functions(config, &simulation.environment);
+    let mut outputs = Outputs::new(config, args).expect("Couldn't create an output file");
+    outputs
+        .write(simulation.step, &simulation.world, config)
+        .unwrap();
+
+    for _ in 0..args.steps {
+        simulation.step(config, &goal_functions, simulation::DELTA_TIME);
+        outputs
+            .write(simulation.step, &simulation.world, config)
+            .unwrap();
+    }
+
+    outputs.finish().unwrap();
+
+    if let Some(path) = &args.golden {
+        let comparison = match args.golden_tolerance {
+            Some(tolerance) => golden::Comparison::Tolerance(tolerance),
+            None => golden::Comparison::Exact,
+        };
+        let expected = golden::read(path).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            std::process::exit(1)
+        });
+
+        match golden::compare(&expected, &outputs.fingerprints, comparison) {
+            Ok(()) => println!("matches {}", path.display()),
+            Err(divergence) => {
+                eprintln!("doesn't match {}, {}", path.display(), divergence);
+                std::process::exit(1)
+            }
+        }
+    }
+}
+
+/// Everything written each step, as chosen on the command line
+struct Outputs {
+    recorder: Option<recording::Recorder<BufWriter<File>>>,
+    csv: Option<export::Csv<BufWriter<File>>>,
+    npy: Option<export::Npy<BufWriter<File>>>,
+    metrics_csv: Option<export::MetricsCsv<BufWriter<File>>>,
+    export_every: u64,
+    print_metrics: bool,
+    print_groups: bool,
+    print_checksums: bool,
+    /// Kept to compare against the golden file once the run is over
+    fingerprints: Vec<golden::Fingerprint>,
+    keep_fingerprints: bool,
+}
+
+impl Outputs {
+    fn new(config: &config::Config, args: &cli::Args) -> io::Result<Outputs> {
+        Ok(Outputs {
+            recorder: args
+                .record
+                .as_ref()
+                .map(|path| recording::create(path, config, args.record_every))
+                .transpose()?,
+            csv: args.csv.as_deref().map(export::create_csv).transpose()?,
+            npy: args.npy.as_deref().map(export::create_npy).transpose()?,
+            metrics_csv: args
+                .metrics_csv
+                .as_deref()
+                .map(export::create_metrics_csv)
+                .transpose()?,
+            export_every: args.export_every.max(1),
+            print_metrics: args.metrics,
+            print_groups: args.groups,
+            print_checksums: args.checksum,
+            fingerprints: Vec::new(),
+            keep_fingerprints: args.golden.is_some(),
+        })
+    }
+
+    fn write(&mut self, step: u64, world: &World, config: &config::Config) -> io::Result<()> {
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record(step, world)?;
+        }
+
+        if step.is_multiple_of(self.export_every) {
+            if let Some(csv) = &mut self.csv {
+                csv.write(step, world)?;
+            }
+            if let Some(npy) = &mut self.npy {
+                npy.write(world)?;
+            }
+        }
+
+        if self.print_metrics || self.metrics_csv.is_some() {
+            let metrics = metrics::measure(world);
+            if self.print_metrics {
+                println!("{}\t{}", step, metrics);
+            }
+            if let Some(metrics_csv) = &mut self.metrics_csv {
+                if step.is_multiple_of(self.export_every) {
+                    metrics_csv.write(step, &metrics)?;
+                }
+            }
+        }
+
+        if self.print_groups {
+            print_groups(step, &groups::find(world, config));
+        }
+
+        if self.print_checksums || self.keep_fingerprints {
+            let fingerprint = golden::fingerprint(step, world);
+            if self.print_checksums {
+                println!("{}", fingerprint);
+            }
+            if self.keep_fingerprints {
+                self.fingerprints.push(fingerprint);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if let Some(recorder) = &mut self.recorder {
+            recorder.flush()?;
+        }
+        if let Some(csv) = &mut self.csv {
+            csv.flush()?;
+        }
+        if let Some(npy) = &mut self.npy {
+            npy.finish()?;
+        }
+        if let Some(metrics_csv) = &mut self.metrics_csv {
+            metrics_csv.flush()?;
+        }
+
+        Ok(())
+    }
+}
+
+pub fn print_groups(step: u64, groups: &[groups::Group]) {
+    let summaries: Vec<String> = groups.iter().map(ToString::to_string).collect();
+    println!(
+        "{}\t{} groups\t{}",
+        step,
+        groups.len(),
+        summaries.join("; ")
+    );
+}
+
+fn sweep(config: &config::Config, steps: u64) {
+    let noises: Vec<f32> = (0..=16)
+        .map(|i| i as f32 * std::f32::consts::PI / 8.0)
+        .collect();
+
+    println!("noise\tpolarisation");
+    for (noise, polarisation) in
+        vicsek::noise_sweep(config, &noises, steps as u32, simulation::DELTA_TIME)
+    {
+        println!("{:.4}\t{:.4}", noise, polarisation);
+    }
+}
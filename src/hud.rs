@@ -0,0 +1,227 @@
+//! Text over the top left of the window: frame rate, step time, population, time scale and
+//! flock metrics, with short-lived messages such as config reloads underneath.
+//! The flock metrics and the panel down the right are slower to work out, so they're only
+//! refreshed every `REFRESH_SECONDS`.
+
+use crate::boids::World;
+use crate::clock::Clock;
+use crate::config;
+use crate::geometry::Model;
+use crate::groups;
+use crate::metrics;
+use crate::text;
+use nalgebra_glm as na;
+
+/// Seconds a toast stays up for
+const TOAST_SECONDS: f32 = 3.0;
+/// Seconds between working out the flock metrics and panel again
+const REFRESH_SECONDS: f32 = 0.5;
+/// Weight of the newest sample in the running averages
+const SMOOTHING: f32 = 0.1;
+/// Window pixels per font pixel
+const SCALE: f32 = 2.0;
+/// Window pixels between the text and the edge of the window
+const MARGIN: f32 = 8.0;
+
+pub struct Hud {
+    pub visible: bool,
+    /// Running averages, in seconds
+    frame_time: f32,
+    step_time: f32,
+    toast: Option<(String, f32)>,
+    /// Flock metric lines and the panel, as of `since_refresh` seconds ago
+    metrics: Vec<String>,
+    panel: Vec<String>,
+    since_refresh: f32,
+}
+
+impl Hud {
+    pub fn new(visible: bool) -> Hud {
+        Hud {
+            visible,
+            frame_time: 0.0,
+            step_time: 0.0,
+            toast: None,
+            metrics: Vec::new(),
+            panel: Vec::new(),
+            since_refresh: REFRESH_SECONDS,
+        }
+    }
+
+    /// Called once a frame with the seconds since the last
+    pub fn frame(&mut self, elapsed: f32) {
+        self.frame_time = average(self.frame_time, elapsed);
+        self.since_refresh += elapsed;
+        self.toast = self
+            .toast
+            .take()
+            .map(|(message, remaining)| (message, remaining - elapsed))
+            .filter(|(_, remaining)| *remaining > 0.0);
+    }
+
+    /// `elapsed` seconds spent running `steps` steps
+    pub fn steps(&mut self, elapsed: f32, steps: u32) {
+        if steps > 0 {
+            self.step_time = average(self.step_time, elapsed / steps as f32);
+        }
+    }
+
+    /// Shows `message` for a few seconds, replacing any earlier one
+    pub fn toast(&mut self, message: String) {
+        self.toast = Some((message, TOAST_SECONDS));
+    }
+
+    /// Works the flock metrics and `panel` out again if they're due, or have been invalidated.
+    /// `panel` is only called then.
+    pub fn update(
+        &mut self,
+        world: &World,
+        config: &config::Config,
+        panel: impl FnOnce() -> Vec<String>,
+    ) {
+        if self.since_refresh < REFRESH_SECONDS {
+            return;
+        }
+
+        self.metrics = vec![
+            format!("polarisation {:.3}", metrics::polarisation(world)),
+            format!("groups {}", groups::find(world, config).len()),
+        ];
+        self.panel = panel();
+        self.since_refresh = 0.0;
+    }
+
+    /// Refreshes on the next `update`, such as when what the panel shows has changed
+    pub fn invalidate(&mut self) {
+        self.since_refresh = REFRESH_SECONDS;
+    }
+
+    pub fn lines(&self, world: &World, clock: &Clock, config: &config::Config) -> Vec<String> {
+        let fps = if self.frame_time > 0.0 {
+            1.0 / self.frame_time
+        } else {
+            0.0
+        };
+        let time = if clock.paused {
+            "paused".to_string()
+        } else {
+            format!("time x{}", clock.time_scale)
+        };
+
+        let mut lines = vec![
+            format!("fps {:.0}", fps),
+            format!("step {:.2} ms", self.step_time * 1000.0),
+            format!("boids {}", world.0.len()),
+            time,
+        ];
+        lines.extend(self.metrics.iter().cloned());
+        if !config.behaviour_active {
+            lines.push("behaviour off".to_string());
+        }
+        if let Some((message, _)) = &self.toast {
+            lines.push(String::new());
+            lines.push(message.clone());
+        }
+
+        lines
+    }
+
+    /// The HUD's lines down the left of the window, and the panel, if any, down the right.
+    /// In window pixels.
+    pub fn models(&self, lines: &[String], window_size: na::Vec2) -> Vec<Model> {
+        if !self.visible {
+            return Vec::new();
+        }
+
+        let colour = na::vec4(0.9, 0.9, 0.9, 1.0);
+        let mut models = vec![text::text(lines, na::vec2(MARGIN, MARGIN), SCALE, colour)];
+        if !self.panel.is_empty() {
+            let left = window_size.x - text::size(&self.panel, SCALE).x - MARGIN;
+            models.push(text::text(
+                &self.panel,
+                na::vec2(left.max(MARGIN), MARGIN),
+                SCALE,
+                colour,
+            ));
+        }
+
+        models
+    }
+}
+
+fn average(average: f32, sample: f32) -> f32 {
+    if average == 0.0 {
+        sample
+    } else {
+        average + SMOOTHING * (sample - average)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation;
+    use crate::test_utils::test_config;
+
+    #[test]
+    fn test_lines_and_toast() {
+        let config = test_config();
+        let simulation = simulation::new(&config);
+        let mut clock = Clock::new();
+        let mut hud = Hud::new(true);
+
+        hud.frame(0.02);
+        hud.steps(0.004, 2);
+        let lines = hud.lines(&simulation.world, &clock, &config);
+        assert_eq!(lines[0], "fps 50");
+        assert_eq!(lines[1], "step 2.00 ms");
+        assert_eq!(lines[2], format!("boids {}", config.population));
+        assert_eq!(lines[3], "time x1");
+
+        hud.toast("config reloaded".to_string());
+        clock.toggle_pause();
+        let lines = hud.lines(&simulation.world, &clock, &config);
+        assert_eq!(lines[3], "paused");
+        assert_eq!(lines.last().unwrap(), "config reloaded");
+
+        hud.frame(TOAST_SECONDS);
+        let lines = hud.lines(&simulation.world, &clock, &config);
+        assert!(!lines.iter().any(|line| line == "config reloaded"));
+    }
+
+    #[test]
+    fn test_metrics_and_panel_refresh_on_a_timer() {
+        let config = test_config();
+        let simulation = simulation::new(&config);
+        let clock = Clock::new();
+        let mut hud = Hud::new(true);
+        let mut refreshes = 0;
+        let mut update = |hud: &mut Hud| {
+            hud.update(&simulation.world, &config, || {
+                refreshes += 1;
+                vec!["boid 3".to_string()]
+            })
+        };
+
+        update(&mut hud);
+        let lines = hud.lines(&simulation.world, &clock, &config);
+        assert!(lines.iter().any(|line| line.starts_with("groups ")));
+        assert_eq!(hud.models(&lines, na::vec2(800.0, 600.0)).len(), 2);
+
+        hud.frame(REFRESH_SECONDS / 2.0);
+        update(&mut hud);
+        hud.frame(REFRESH_SECONDS / 2.0);
+        update(&mut hud);
+        hud.invalidate();
+        update(&mut hud);
+        assert_eq!(refreshes, 3);
+    }
+
+    #[test]
+    fn test_hidden_hud_draws_nothing() {
+        let mut hud = Hud::new(false);
+        hud.panel = vec!["boid 3".to_string()];
+        let lines = vec!["fps 60".to_string()];
+        assert!(hud.models(&lines, na::vec2(800.0, 600.0)).is_empty());
+    }
+}
@@ -0,0 +1,227 @@
+use crate::overlays::Overlay;
+use gl::glutin::VirtualKeyCode;
+use glium as gl;
+use serde::{Deserialize, Serialize};
+
+/// Things the user can ask of the running simulation from the keyboard
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    Pause,
+    /// Pauses and runs a single step
+    Step,
+    Faster,
+    Slower,
+    /// Starts again from the generated world, scenario or snapshot the run started from
+    Reset,
+    ToggleBehaviour,
+    SaveSnapshot,
+    LoadSnapshot,
+    SpawnBoid,
+    /// Removes the most recently spawned boid
+    DespawnBoid,
+    ToggleTrails,
+    ToggleOverlay(Overlay),
+    /// Moves the selection on to the boid with the next id
+    CycleSelection,
+    /// Prints the selected boid's state
+    Inspect,
+    ToggleHud,
+    /// Follows the flock's centroid, or stops following it
+    FollowCentroid,
+    /// Follows the boid nearest the cursor, or stops following it
+    FollowBoid,
+    ResetCamera,
+    /// Replay only: jumps back a few seconds
+    SeekBackward,
+    /// Replay only: jumps forward a few seconds
+    SeekForward,
+    /// Replay only
+    JumpToStart,
+    /// Replay only
+    JumpToEnd,
+    /// Replay only: pauses and goes back a single frame
+    StepBack,
+}
+
+/// The `[keys]` section of the config. Keys are named as in glutin's `VirtualKeyCode`,
+/// such as "Space", "P", "Key1" or "F5". Any left out keep their default binding.
+/// Replays also use `pause`, `step`, `faster` and `slower`, so no two actions may share a key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Keys {
+    pub pause: VirtualKeyCode,
+    pub step: VirtualKeyCode,
+    pub faster: VirtualKeyCode,
+    pub slower: VirtualKeyCode,
+    pub reset: VirtualKeyCode,
+    pub toggle_behaviour: VirtualKeyCode,
+    pub save_snapshot: VirtualKeyCode,
+    pub load_snapshot: VirtualKeyCode,
+    pub spawn_boid: VirtualKeyCode,
+    pub despawn_boid: VirtualKeyCode,
+    pub toggle_trails: VirtualKeyCode,
+    pub overlay_velocity: VirtualKeyCode,
+    pub overlay_drag: VirtualKeyCode,
+    pub overlay_force: VirtualKeyCode,
+    pub overlay_influence: VirtualKeyCode,
+    pub overlay_field_of_view: VirtualKeyCode,
+    pub overlay_goals: VirtualKeyCode,
+    pub overlay_selected_only: VirtualKeyCode,
+    pub cycle_selection: VirtualKeyCode,
+    pub inspect: VirtualKeyCode,
+    pub toggle_hud: VirtualKeyCode,
+    pub follow_centroid: VirtualKeyCode,
+    pub follow_boid: VirtualKeyCode,
+    pub reset_camera: VirtualKeyCode,
+    pub seek_backward: VirtualKeyCode,
+    pub seek_forward: VirtualKeyCode,
+    pub jump_to_start: VirtualKeyCode,
+    pub jump_to_end: VirtualKeyCode,
+    pub step_back: VirtualKeyCode,
+}
+
+impl Default for Keys {
+    fn default() -> Keys {
+        Keys {
+            pause: VirtualKeyCode::Space,
+            step: VirtualKeyCode::Period,
+            faster: VirtualKeyCode::RBracket,
+            slower: VirtualKeyCode::LBracket,
+            reset: VirtualKeyCode::R,
+            toggle_behaviour: VirtualKeyCode::B,
+            save_snapshot: VirtualKeyCode::F5,
+            load_snapshot: VirtualKeyCode::F9,
+            spawn_boid: VirtualKeyCode::Equals,
+            despawn_boid: VirtualKeyCode::Minus,
+            toggle_trails: VirtualKeyCode::T,
+            overlay_velocity: VirtualKeyCode::Key1,
+            overlay_drag: VirtualKeyCode::Key2,
+            overlay_force: VirtualKeyCode::Key3,
+            overlay_influence: VirtualKeyCode::Key4,
+            overlay_field_of_view: VirtualKeyCode::Key7,
+            overlay_goals: VirtualKeyCode::Key5,
+            overlay_selected_only: VirtualKeyCode::Key6,
+            cycle_selection: VirtualKeyCode::Tab,
+            inspect: VirtualKeyCode::I,
+            toggle_hud: VirtualKeyCode::H,
+            follow_centroid: VirtualKeyCode::C,
+            follow_boid: VirtualKeyCode::F,
+            reset_camera: VirtualKeyCode::Key0,
+            seek_backward: VirtualKeyCode::Left,
+            seek_forward: VirtualKeyCode::Right,
+            jump_to_start: VirtualKeyCode::Home,
+            jump_to_end: VirtualKeyCode::End,
+            step_back: VirtualKeyCode::Comma,
+        }
+    }
+}
+
+impl Keys {
+    fn bindings(&self) -> [(VirtualKeyCode, Action); 29] {
+        [
+            (self.pause, Action::Pause),
+            (self.step, Action::Step),
+            (self.faster, Action::Faster),
+            (self.slower, Action::Slower),
+            (self.reset, Action::Reset),
+            (self.toggle_behaviour, Action::ToggleBehaviour),
+            (self.save_snapshot, Action::SaveSnapshot),
+            (self.load_snapshot, Action::LoadSnapshot),
+            (self.spawn_boid, Action::SpawnBoid),
+            (self.despawn_boid, Action::DespawnBoid),
+            (self.toggle_trails, Action::ToggleTrails),
+            (
+                self.overlay_velocity,
+                Action::ToggleOverlay(Overlay::Velocity),
+            ),
+            (self.overlay_drag, Action::ToggleOverlay(Overlay::Drag)),
+            (self.overlay_force, Action::ToggleOverlay(Overlay::Force)),
+            (
+                self.overlay_influence,
+                Action::ToggleOverlay(Overlay::Influence),
+            ),
+            (
+                self.overlay_field_of_view,
+                Action::ToggleOverlay(Overlay::FieldOfView),
+            ),
+            (self.overlay_goals, Action::ToggleOverlay(Overlay::Goals)),
+            (
+                self.overlay_selected_only,
+                Action::ToggleOverlay(Overlay::SelectedOnly),
+            ),
+            (self.cycle_selection, Action::CycleSelection),
+            (self.inspect, Action::Inspect),
+            (self.toggle_hud, Action::ToggleHud),
+            (self.follow_centroid, Action::FollowCentroid),
+            (self.follow_boid, Action::FollowBoid),
+            (self.reset_camera, Action::ResetCamera),
+            (self.seek_backward, Action::SeekBackward),
+            (self.seek_forward, Action::SeekForward),
+            (self.jump_to_start, Action::JumpToStart),
+            (self.jump_to_end, Action::JumpToEnd),
+            (self.step_back, Action::StepBack),
+        ]
+    }
+
+    /// Fails if a key is bound to more than one action
+    pub fn validate(&self) -> Result<(), String> {
+        let bindings = self.bindings();
+        for (i, (key, action)) in bindings.iter().enumerate() {
+            if let Some((_, other)) = bindings[i + 1..].iter().find(|(other, _)| other == key) {
+                return Err(format!(
+                    "{:?} is bound to both {:?} and {:?}",
+                    key, action, other
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The first action bound to `key`
+pub fn action(key: VirtualKeyCode, keys: &Keys) -> Option<Action> {
+    keys.bindings()
+        .iter()
+        .find(|(bound, _)| *bound == key)
+        .map(|(_, action)| *action)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::test_config;
+
+    #[test]
+    fn test_default_bindings_are_distinct() {
+        let keys = test_config().keys;
+        let bindings = keys.bindings();
+        for (key, action) in bindings.iter() {
+            assert_eq!(super::action(*key, &keys), Some(*action));
+        }
+        assert_eq!(super::action(VirtualKeyCode::Z, &keys), None);
+        assert!(keys.validate().is_ok());
+    }
+
+    #[test]
+    fn test_rejects_a_key_bound_twice() {
+        let keys = Keys {
+            follow_boid: VirtualKeyCode::T,
+            ..test_config().keys
+        };
+        assert_eq!(
+            keys.validate(),
+            Err("T is bound to both ToggleTrails and FollowBoid".to_string())
+        );
+    }
+
+    #[test]
+    fn test_rebinding() {
+        let keys = Keys {
+            pause: VirtualKeyCode::P,
+            ..test_config().keys
+        };
+        assert_eq!(action(VirtualKeyCode::P, &keys), Some(Action::Pause));
+        assert_eq!(action(VirtualKeyCode::Space, &keys), None);
+    }
+}
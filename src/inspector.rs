@@ -0,0 +1,114 @@
+//! A readout of one boid's state and of what each goal is asking of it, for working out
+//! why it's doing what it's doing.
+
+use crate::boids;
+use crate::boids::goals::GoalFunction;
+use crate::boids::World;
+use crate::config;
+use crate::utilities::mean;
+use nalgebra_glm as na;
+
+fn vector(v: &na::Vec2) -> String {
+    format!("({:.3}, {:.3})", v.x, v.y)
+}
+
+/// One line per value. Goals beyond the end of `goal_names` are listed as "other". `pulls`, such
+/// as the mouse's while a button is held, are listed together as "pointer" and added to the
+/// resultant goal after averaging, as they are when stepping.
+pub fn describe(
+    world: &World,
+    index: usize,
+    config: &config::Config,
+    goal_functions: &[GoalFunction],
+    pulls: &[GoalFunction],
+    goal_names: &[&'static str],
+) -> Vec<String> {
+    let boid = &world.0[index];
+    let contributions = boids::goal_contributions(world, index, config, goal_functions);
+    let neighbours = boids::neighbours(world, index, config);
+
+    let mut lines = vec![
+        format!("boid {}", boid.id),
+        format!("position {}", vector(&boid.pos)),
+        format!(
+            "velocity {} speed {:.3}",
+            vector(&boid.vel),
+            na::magnitude(&boid.vel)
+        ),
+        format!(
+            "heading {:.1} degrees",
+            boid.rot.y.atan2(boid.rot.x).to_degrees()
+        ),
+        format!("angular velocity {:.3}", boid.angular_vel),
+        format!("area {:.3}", boid.area),
+        format!("neighbours {}", neighbours.len()),
+    ];
+    lines.extend(contributions.iter().enumerate().map(|(i, goal)| {
+        let name = goal_names.get(i).unwrap_or(&"other");
+        format!("  {} {}", name, vector(goal))
+    }));
+    if !pulls.is_empty() {
+        let pull: na::Vec2 = pulls.iter().map(|pull| pull(boid, &[]).0).sum();
+        lines.push(format!("  pointer {}", vector(&pull)));
+    }
+    let resultant = boids::add_pulls(boid, mean(&contributions), pulls);
+    lines.push(format!("resultant goal {}", vector(&resultant)));
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation;
+    use crate::test_utils::test_config;
+
+    #[test]
+    fn test_describe_names_every_goal() {
+        let config = test_config();
+        let simulation = simulation::new(&config);
+        let goal_functions = simulation::goal_functions(&config, &simulation.environment);
+        let names = simulation::goal_names(&simulation.environment);
+
+        let lines = describe(&simulation.world, 2, &config, &goal_functions, &[], &names);
+        assert_eq!(lines[0], "boid 2");
+        for name in &names {
+            assert!(lines
+                .iter()
+                .any(|line| line.starts_with(&format!("  {} (", name))));
+        }
+        assert!(lines.last().unwrap().starts_with("resultant goal"));
+        assert!(!lines.iter().any(|line| line.starts_with("  pointer")));
+
+        let lines = describe(
+            &simulation.world,
+            2,
+            &config,
+            &goal_functions,
+            &[],
+            &names[..1],
+        );
+        assert!(lines.iter().any(|line| line.starts_with("  other (")));
+
+        let contributions =
+            boids::goal_contributions(&simulation.world, 2, &config, &goal_functions);
+        let pulls: Vec<GoalFunction> =
+            vec![Box::new(|_, _| boids::goals::Goal(na::vec2(0.5, 0.0)))];
+        let lines = describe(
+            &simulation.world,
+            2,
+            &config,
+            &goal_functions,
+            &pulls,
+            &names,
+        );
+        assert!(lines.contains(&"  pointer (0.500, 0.000)".to_string()));
+        assert_eq!(
+            lines.last().unwrap(),
+            &format!(
+                "resultant goal {}",
+                vector(&(mean(&contributions) + na::vec2(0.5, 0.0)))
+            )
+        );
+    }
+}
@@ -0,0 +1,163 @@
+//! Checks that every boid is still in a valid state after a step. A single NaN spreads to the
+//! whole flock within a few steps, so this stops at the first one and works out where it came from.
+
+use crate::boids;
+use crate::boids::goals::GoalFunction;
+use crate::boids::World;
+use crate::config;
+use crate::physics::Entity;
+use nalgebra_glm as na;
+use std::fmt;
+
+/// The first boid found in an invalid state
+#[derive(Debug)]
+pub struct Violation {
+    pub step: u64,
+    pub index: usize,
+    pub entity: Entity,
+    pub problem: &'static str,
+    /// The first goal that was already invalid for this boid before the step, if any
+    pub goal: Option<(&'static str, na::Vec2)>,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "boid {} {} after step {}: {:?}",
+            self.index, self.problem, self.step, self.entity
+        )?;
+        match self.goal {
+            Some((name, goal)) => write!(f, "\nfirst invalid goal: {} = {:?}", name, goal),
+            None => write!(f, "\nevery goal was valid before the step"),
+        }
+    }
+}
+
+fn finite(vec: &na::Vec2) -> bool {
+    vec.x.is_finite() && vec.y.is_finite()
+}
+
+/// What's wrong with the boid, if anything
+pub fn problem(entity: &Entity) -> Option<&'static str> {
+    if !finite(&entity.pos) {
+        Some("has a non-finite position")
+    } else if !finite(&entity.vel) {
+        Some("has a non-finite velocity")
+    } else if !finite(&entity.rot) {
+        Some("has a non-finite heading")
+    } else if entity.rot == na::zero() {
+        Some("has no heading")
+    } else if !entity.angular_vel.is_finite() {
+        Some("has a non-finite angular velocity")
+    } else if !finite(&entity.resultant_force) {
+        Some("has a non-finite resultant force")
+    } else if !entity.wander_angle.is_finite() {
+        Some("has a non-finite wander angle")
+    } else if !(entity.mass.is_finite() && entity.mass > 0.0) {
+        Some("has a non-positive mass")
+    } else if !entity.area.is_finite() {
+        Some("has a non-finite area")
+    } else {
+        None
+    }
+}
+
+/// Checks `after`, blaming goals evaluated on `before` when given. `goal_names` are in the same
+/// order as `goal_functions`.
+pub fn check(
+    step: u64,
+    before: Option<&World>,
+    after: &World,
+    config: &config::Config,
+    goal_functions: &[GoalFunction],
+    goal_names: &[&'static str],
+) -> Result<(), Violation> {
+    let (index, entity, problem) = match after
+        .0
+        .iter()
+        .enumerate()
+        .find_map(|(index, entity)| problem(entity).map(|problem| (index, entity, problem)))
+    {
+        Some(found) => found,
+        None => return Ok(()),
+    };
+
+    let goal = before
+        .filter(|before| index < before.0.len())
+        .and_then(|before| {
+            boids::goal_contributions(before, index, config, goal_functions)
+                .into_iter()
+                .zip(goal_names)
+                .map(|(goal, name)| (*name, goal))
+                .find(|(_, goal)| !finite(goal))
+        });
+
+    Err(Violation {
+        step,
+        index,
+        entity: *entity,
+        problem,
+        goal,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::boids::goals::Goal;
+    use crate::config::Config;
+    use crate::generation;
+    use crate::scenario::{Environment, GoalPoint};
+    use crate::simulation;
+
+    #[test]
+    fn test_reports_first_invalid_boid_and_goal() {
+        let before = World(vec![Entity::default(); 3]);
+        let mut after = World(vec![Entity::default(); 3]);
+        after.0[1].vel.x = f32::NAN;
+        after.0[2].pos.y = f32::INFINITY;
+
+        let goal_functions: Vec<GoalFunction> = vec![
+            Box::new(|_, _| Goal(na::vec2(1.0, 0.0))),
+            Box::new(|_, _| Goal(na::vec2(f32::NAN, 0.0))),
+        ];
+        let names = ["fine", "broken"];
+        let config = crate::test_utils::test_config();
+
+        let violation =
+            check(7, Some(&before), &after, &config, &goal_functions, &names).unwrap_err();
+        assert_eq!((violation.step, violation.index), (7, 1));
+        assert_eq!(violation.problem, "has a non-finite velocity");
+        assert_eq!(violation.goal.map(|(name, _)| name), Some("broken"));
+
+        assert!(check(7, Some(&before), &before, &config, &goal_functions, &names).is_ok());
+        assert!(check(7, None, &after, &config, &[], &[])
+            .unwrap_err()
+            .goal
+            .is_none());
+    }
+
+    #[test]
+    fn test_stacked_boids_stay_valid() {
+        let config = Config {
+            check_invariants: true,
+            ..crate::test_utils::test_config()
+        };
+        let environment = Environment {
+            goals: vec![GoalPoint {
+                position: na::zero(),
+                weight: 1.0,
+            }],
+            ..Default::default()
+        };
+        let world = World(vec![Entity::default(); 5]);
+        let mut simulation =
+            simulation::with_world(&config, world, generation::seeded_rng(1), environment);
+        let goal_functions = simulation::goal_functions(&config, &simulation.environment);
+
+        for _ in 0..100 {
+            simulation.step(&config, &goal_functions, simulation::DELTA_TIME);
+        }
+    }
+}
@@ -5,123 +5,426 @@ use nalgebra_glm as na;
 use std::time::Instant;
 
 mod boids;
+mod camera;
+mod cli;
+mod clock;
 mod config;
 mod draw;
+mod export;
 mod generation;
 mod geometry;
+mod golden;
+mod groups;
+mod headless;
+mod hud;
+mod input;
+mod inspector;
+mod invariants;
+mod metrics;
+mod overlays;
 mod physics;
+mod pointer;
+mod recording;
+mod replay;
+mod scenario;
+mod selection;
+mod simulation;
+mod snapshot;
 mod test_utils;
+mod text;
+mod trails;
 mod utilities;
-use physics::Entity;
 use utilities::*;
 
 fn main() {
+    let args = cli::parse(std::env::args().skip(1)).unwrap_or_else(|error| {
+        eprintln!("{}\n{}", error, cli::USAGE);
+        std::process::exit(2)
+    });
+
     let executable_directory = utilities::executable_directory().unwrap();
     let config_file_path = executable_directory.join(std::path::Path::new("boids.toml"));
     let config_file_path_str = config_file_path
         .to_str()
         .expect("Tried to convert config file path to str, but it contains non UTF8 characters");
 
-    let mut session_config = config::read(config_file_path_str);
-    let (config_receiver, _hotwatch) = config::watch(config_file_path_str.to_owned());
-
-    let influence_radius = boids::goals::InfluenceRadius(session_config.influence_radius);
-
-    let goal_functions: Vec<Box<dyn Fn(&physics::Entity, &[&Entity]) -> boids::goals::Goal>> = vec![
-        //Box::new(|boid, _| boids::goals::static_goal(boid, na::vec2(0.75, 0.0))),
-        Box::new(|_, world| boids::goals::center_of_mass(world, influence_radius)),
-        Box::new(|_, world| boids::goals::keep_distance(world, influence_radius, 0.025)),
-        Box::new(|_, world| {
-            boids::goals::Goal(boids::goals::same_direction(world, influence_radius).0 * 1.5)
-        }),
-        Box::new(|boid, _| {
-            boids::goals::Goal(boids::goals::bound(boid, na::vec2(1.0, 1.0), 0.3).0 * 20.0)
-        }),
-    ];
-
-    fn config_model_funcs(
-        config: &config::Config,
-    ) -> Vec<Box<dyn Fn(&physics::Entity) -> geometry::Model>> {
-        let drag_coefficient = config.drag_coefficient;
-        let max_force = config.max_force;
-
-        vec![
-            Box::new(|entity| {
-                geometry::boid().map(|v| {
-                    let matrix = geometry::position_rotation_to_matrix(&entity.pos, &entity.rot);
-                    matrix * na::scaling2d(&na::vec2(0.005, 0.005)) * v
-                })
-            }),
-            /*Box::new(move |entity| {
-                geometry::arrow_vector(
-                    &(4.0 * physics::drag_force(0.25, drag_coefficient, entity.vel)),
-                )
-                .map(|v| na::translation2d(&entity.pos) * na::scaling2d(&na::vec2(0.1, 0.1)) * v)
-            }),
-            Box::new(|entity| {
-                geometry::arrow_vector(&entity.vel).map(|v| {
-                    na::translation2d(&entity.pos) * na::scaling2d(&na::vec2(0.1, 0.1)) * v
-                })
-            }),
-            Box::new(move |entity| {
-                geometry::arrow_vector(
-                    &((entity.resultant_force
-                        - physics::drag_force(0.25, drag_coefficient, entity.vel))
-                        * max_force
-                        * 4.0),
-                )
-                .map(|v| na::translation2d(&entity.pos) * na::scaling2d(&na::vec2(0.1, 0.1)) * v)
-            }),*/
-        ]
+    let (mut session_config, start, mut simulation) = match &args.load {
+        Some(path) => {
+            let snapshot = snapshot::load(path).expect("Couldn't load the snapshot");
+            let start = Start::Snapshot {
+                path: path.clone(),
+                seed: snapshot.config.seed,
+            };
+            (snapshot.config, start, snapshot.simulation)
+        }
+        None => {
+            let config = config::read(config_file_path_str);
+            match &args.scenario {
+                Some(path) => {
+                    let scenario = scenario::read(path).expect("Couldn't read the scenario");
+                    let config = scenario::config(&scenario, config);
+                    let simulation = scenario::simulation(&scenario, &config);
+                    (config, Start::Scenario(Box::new(scenario)), simulation)
+                }
+                None => {
+                    let simulation = simulation::new(&config);
+                    (config, Start::Generated, simulation)
+                }
+            }
+        }
+    };
+    if args.headless || args.vicsek_sweep {
+        headless::run(&session_config, simulation, &args);
+        return;
     }
 
-    let mut model_funcs = config_model_funcs(&session_config);
+    let (config_receiver, _hotwatch) = config::watch(config_file_path_str.to_owned());
+
+    let mut goal_functions = simulation::goal_functions(&session_config, &simulation.environment);
 
     let (display, mut events_loop) =
         new_window("Boids", glutin::dpi::LogicalSize::new(1024.0, 768.0));
     let program = draw::simple_program(&display).unwrap();
 
     let mut drawer = draw::drawer(display, program);
+    let mut camera = camera::Camera::new(na::vec2(1024.0, 768.0));
+
+    if let Some(path) = &args.replay {
+        replay::run(
+            path,
+            &session_config.keys,
+            &mut drawer,
+            &mut events_loop,
+            &mut camera,
+        );
+        return;
+    }
 
-    let mut world = generation::random_world(
-        session_config.population,
-        na::vec2(-1.0, -1.0),
-        na::vec2(1.0, 1.0),
-    );
+    let quick_snapshot_path = executable_directory.join(snapshot::QUICK_SNAPSHOT);
+    let mut recorder = args.record.as_ref().map(|path| {
+        recording::create(path, &session_config, args.record_every)
+            .expect("Couldn't create the recording file")
+    });
+    if let Some(recorder) = &mut recorder {
+        recorder.record(simulation.step, &simulation.world).unwrap();
+    }
+    let mut trails = trails::Trails::new(session_config.trail_length);
+    let mut show_trails = session_config.trails;
+    let mut overlays = overlays::Overlays::from_config(&session_config);
+    let mut selected = None;
+    let mut clock = clock::Clock::new();
+    let mut pointer = pointer::Pointer::default();
+    let mut hud = hud::Hud::new(session_config.hud);
     let mut delta = Instant::now();
 
     let mut closed = false;
     while !closed {
+        let mut keys = Vec::new();
+        let mut clicks = Vec::new();
         events_loop.poll_events(|e| {
             if window_closed(&e) {
                 closed = true
             }
+            keys.extend(key_pressed(&e));
+            camera.handle_event(&e);
+            clicks.extend(pointer.handle_event(&e));
         });
-
-        if let Ok(new_config) = config_receiver.try_recv() {
-            println!("config file read");
-            session_config = new_config;
-            model_funcs = config_model_funcs(&session_config);
+        let cursor = camera.cursor_in_world();
+        // Only while a button is held
+        let pulls: Vec<_> = pointer
+            .goal_function(cursor, &session_config)
+            .into_iter()
+            .collect();
+        for click in clicks {
+            match click {
+                pointer::Click::Burst => {
+                    let burst_radius = na::vec2(1.0, 1.0) * session_config.spawn_burst_radius;
+                    for _ in 0..session_config.spawn_burst {
+                        simulation.spawn_random(
+                            &session_config,
+                            cursor - burst_radius,
+                            cursor + burst_radius,
+                        );
+                    }
+                }
+                pointer::Click::Select => {
+                    let radius = selection::PICK_RADIUS * camera.half_height;
+                    selected = selection::at(&simulation.world, cursor, radius);
+                    hud.invalidate();
+                    inspect(
+                        &simulation,
+                        &session_config,
+                        &goal_functions,
+                        &pulls,
+                        selected,
+                    );
+                }
+            }
+        }
+        let actions: Vec<_> = keys
+            .into_iter()
+            .filter_map(|key| input::action(key, &session_config.keys))
+            .collect();
+        for action in actions {
+            match action {
+                input::Action::Pause => clock.toggle_pause(),
+                input::Action::Step => clock.step_once(),
+                input::Action::Faster => clock.faster(),
+                input::Action::Slower => clock.slower(),
+                input::Action::Reset => match start.simulation(&session_config) {
+                    Ok(restarted) => {
+                        simulation = restarted;
+                        hud.invalidate();
+                        goal_functions =
+                            simulation::goal_functions(&session_config, &simulation.environment);
+                        trails.clear();
+                        stop_recording(&mut recorder);
+                    }
+                    Err(error) => println!("couldn't reset: {}", error),
+                },
+                input::Action::ToggleBehaviour => {
+                    session_config.behaviour_active = !session_config.behaviour_active;
+                }
+                input::Action::SaveSnapshot => {
+                    match snapshot::save(&quick_snapshot_path, &session_config, &simulation) {
+                        Ok(()) => println!("saved {}", quick_snapshot_path.display()),
+                        Err(error) => println!("couldn't save the snapshot: {}", error),
+                    }
+                }
+                input::Action::LoadSnapshot => match snapshot::load(&quick_snapshot_path) {
+                    Ok(snapshot) => {
+                        println!("loaded {}", quick_snapshot_path.display());
+                        session_config = snapshot.config;
+                        simulation = snapshot.simulation;
+                        hud.invalidate();
+                        goal_functions =
+                            simulation::goal_functions(&session_config, &simulation.environment);
+                        overlays = overlays::Overlays::from_config(&session_config);
+                        trails.clear();
+                        stop_recording(&mut recorder);
+                    }
+                    Err(error) => println!("couldn't load the snapshot: {}", error),
+                },
+                input::Action::SpawnBoid => {
+                    let id = simulation.spawn_random(
+                        &session_config,
+                        na::vec2(-1.0, -1.0),
+                        na::vec2(1.0, 1.0),
+                    );
+                    println!("spawned boid {}", id);
+                }
+                input::Action::DespawnBoid => {
+                    let newest = simulation.world.0.iter().map(|boid| boid.id).max();
+                    if let Some(boid) = newest.and_then(|id| simulation.despawn(id)) {
+                        println!("despawned boid {}", boid.id);
+                    }
+                }
+                input::Action::ToggleTrails => {
+                    show_trails = !show_trails;
+                    trails.clear();
+                }
+                input::Action::ToggleOverlay(overlay) => overlays.toggle(overlay),
+                input::Action::CycleSelection => {
+                    selected = selection::next(&simulation.world, selected);
+                    hud.invalidate();
+                    inspect(
+                        &simulation,
+                        &session_config,
+                        &goal_functions,
+                        &pulls,
+                        selected,
+                    );
+                }
+                input::Action::Inspect => {
+                    inspect(
+                        &simulation,
+                        &session_config,
+                        &goal_functions,
+                        &pulls,
+                        selected,
+                    );
+                }
+                input::Action::ToggleHud => hud.visible = !hud.visible,
+                input::Action::FollowCentroid
+                | input::Action::FollowBoid
+                | input::Action::ResetCamera => {
+                    camera.handle_action(action, &simulation.world, selected)
+                }
+                input::Action::SeekBackward
+                | input::Action::SeekForward
+                | input::Action::JumpToStart
+                | input::Action::JumpToEnd
+                | input::Action::StepBack => (),
+            }
         }
 
-        if delta.elapsed().as_secs_f64() >= 0.016 {
-            if session_config.behaviour_active {
-                world = boids::step_world(&world, &session_config, &goal_functions[..]);
+        match config_receiver.try_recv() {
+            Ok(Ok(new_config)) => {
+                println!("config file read");
+                hud.toast("config reloaded".to_string());
+                session_config = start.config(new_config);
+                hud.invalidate();
+                goal_functions =
+                    simulation::goal_functions(&session_config, &simulation.environment);
+                overlays = overlays::Overlays::from_config(&session_config);
+                trails.length = session_config.trail_length;
             }
+            Ok(Err(error)) => {
+                println!("couldn't reload the config: {}", error);
+                hud.toast("config reload failed, see the terminal".to_string());
+            }
+            Err(_) => (),
+        }
 
-            physics::step_world(&mut world, 0.016, &session_config);
-            delta = Instant::now();
+        let elapsed = delta.elapsed().as_secs_f32();
+        delta = Instant::now();
+        hud.frame(elapsed);
+        let steps = clock.advance(elapsed);
+        let stepping = Instant::now();
+        for _ in 0..steps {
+            simulation.step_with_pulls(
+                &session_config,
+                &goal_functions,
+                &pulls,
+                simulation::DELTA_TIME,
+            );
+            if let Some(recorder) = &mut recorder {
+                recorder.record(simulation.step, &simulation.world).unwrap();
+            }
+            if show_trails {
+                trails.record(&simulation.world);
+            }
+            if args.metrics {
+                println!(
+                    "{}\t{}",
+                    simulation.step,
+                    metrics::measure(&simulation.world)
+                );
+            }
+            if args.groups {
+                headless::print_groups(
+                    simulation.step,
+                    &groups::find(&simulation.world, &session_config),
+                );
+            }
         }
+        hud.steps(stepping.elapsed().as_secs_f32(), steps);
 
-        let models = geometry::world_to_models(&world, &model_funcs);
+        camera.update(&simulation.world);
+        let colours = geometry::boid_colours(&simulation.world, &session_config);
+        drawer.add_boids(&simulation.world, &colours);
+        let models = overlays.models(
+            &simulation.world,
+            selected,
+            &session_config,
+            &goal_functions,
+        );
+        if show_trails {
+            drawer.add_vertices(
+                gl::index::PrimitiveType::LinesList,
+                trails.vertices(&simulation.world, &colours),
+            );
+        }
 
         for model in models {
             drawer.add_model(model);
         }
+        for model in geometry::environment_models(&simulation.environment) {
+            drawer.add_model(model);
+        }
 
         let mut frame = drawer.display.draw();
         frame.clear_color(0.0, 0.0, 0.0, 1.0);
-        drawer.draw(&mut frame);
+        drawer.draw(&mut frame, &camera.view_projection());
+        if hud.visible {
+            hud.update(&simulation.world, &session_config, || {
+                match selected.and_then(|id| simulation.world.index_of(id)) {
+                    Some(index) => inspector::describe(
+                        &simulation.world,
+                        index,
+                        &session_config,
+                        &goal_functions,
+                        &pulls,
+                        &simulation::goal_names(&simulation.environment),
+                    ),
+                    None => Vec::new(),
+                }
+            });
+            let lines = hud.lines(&simulation.world, &clock, &session_config);
+            for model in hud.models(&lines, camera.window_size()) {
+                drawer.add_model(model);
+            }
+            drawer.draw(&mut frame, &camera.screen_projection());
+        }
         frame.finish().unwrap();
     }
+
+    if let Some(recorder) = &mut recorder {
+        recorder.flush().unwrap();
+    }
+}
+
+/// What the run started from, so that resetting goes back to it
+enum Start {
+    Generated,
+    Scenario(Box<scenario::Scenario>),
+    /// Read again on every reset
+    Snapshot {
+        path: std::path::PathBuf,
+        seed: u64,
+    },
+}
+
+impl Start {
+    /// The config to run with once boids.toml has been reloaded as `reloaded`.
+    /// A scenario's own config and seed still win over it. A snapshot run takes up boids.toml's
+    /// settings, so that a resumed run can be tuned as it goes, but keeps the snapshot's seed,
+    /// as its random state carries on from the snapshot rather than starting again from a seed.
+    fn config(&self, reloaded: config::Config) -> config::Config {
+        match self {
+            Start::Generated => reloaded,
+            Start::Scenario(scenario) => scenario::config(scenario, reloaded),
+            Start::Snapshot { seed, .. } => config::Config {
+                seed: *seed,
+                ..reloaded
+            },
+        }
+    }
+
+    /// The simulation as it was at the start, under the current `config`
+    fn simulation(&self, config: &config::Config) -> std::io::Result<simulation::Simulation> {
+        match self {
+            Start::Generated => Ok(simulation::new(config)),
+            Start::Scenario(scenario) => Ok(scenario::simulation(scenario, config)),
+            Start::Snapshot { path, .. } => {
+                snapshot::load(path).map(|snapshot| snapshot.simulation)
+            }
+        }
+    }
+}
+
+/// Finishes the recording, if there is one, as the steps recorded so far no longer lead on
+/// to the simulation's
+fn stop_recording<W: std::io::Write>(recorder: &mut Option<recording::Recorder<W>>) {
+    if let Some(mut recorder) = recorder.take() {
+        recorder.flush().unwrap();
+        println!("stopped recording, since the simulation no longer follows on from it");
+    }
+}
+
+/// Prints the selected boid's state, if it's still around
+fn inspect(
+    simulation: &simulation::Simulation,
+    config: &config::Config,
+    goal_functions: &[boids::goals::GoalFunction],
+    pulls: &[boids::goals::GoalFunction],
+    selected: Option<u32>,
+) {
+    if let Some(index) = selected.and_then(|id| simulation.world.index_of(id)) {
+        let names = simulation::goal_names(&simulation.environment);
+        let world = &simulation.world;
+        for line in inspector::describe(world, index, config, goal_functions, pulls, &names) {
+            println!("{}", line);
+        }
+    }
 }
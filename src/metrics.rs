@@ -0,0 +1,203 @@
+use crate::boids::World;
+use crate::physics::Entity;
+use crate::utilities::{mean, normalize_or_zero};
+use nalgebra_glm as na;
+use std::fmt;
+
+/// Order parameters describing the flock as a whole
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Metrics {
+    /// 1 when every boid shares a heading, near 0 when headings are random
+    pub polarisation: f32,
+    /// 1 when every boid circles the centroid in the same direction, near 0 otherwise
+    pub milling: f32,
+    pub mean_nearest_neighbour_distance: f32,
+    pub radius_of_gyration: f32,
+    pub mean_speed: f32,
+    pub speed_std_dev: f32,
+    pub min_speed: f32,
+    pub max_speed: f32,
+}
+
+pub fn measure(world: &World) -> Metrics {
+    let speeds: Vec<f32> = world
+        .0
+        .iter()
+        .map(|boid| na::magnitude(&boid.vel))
+        .collect();
+    let mean_speed = mean_f32(&speeds);
+    let speed_variance = mean_f32(
+        &speeds
+            .iter()
+            .map(|speed| (speed - mean_speed).powi(2))
+            .collect::<Vec<f32>>(),
+    );
+
+    let min_speed = if speeds.is_empty() {
+        0.0
+    } else {
+        speeds.iter().cloned().fold(f32::INFINITY, f32::min)
+    };
+
+    Metrics {
+        polarisation: polarisation(world),
+        milling: milling(world),
+        mean_nearest_neighbour_distance: mean_nearest_neighbour_distance(world),
+        radius_of_gyration: radius_of_gyration(world),
+        mean_speed,
+        speed_std_dev: speed_variance.sqrt(),
+        min_speed,
+        max_speed: speeds.iter().cloned().fold(0.0, f32::max),
+    }
+}
+
+fn mean_f32(values: &[f32]) -> f32 {
+    if values.is_empty() {
+        0.0
+    } else {
+        values.iter().sum::<f32>() / values.len() as f32
+    }
+}
+
+fn heading(boid: &Entity) -> na::Vec2 {
+    normalize_or_zero(&boid.rot)
+}
+
+pub fn centroid(World(boids): &World) -> na::Vec2 {
+    let positions: Vec<na::Vec2> = boids.iter().map(|boid| boid.pos).collect();
+    mean(&positions)
+}
+
+pub fn polarisation(World(boids): &World) -> f32 {
+    let headings: Vec<na::Vec2> = boids.iter().map(heading).collect();
+    na::magnitude(&mean(&headings))
+}
+
+/// Normalised angular momentum about the centroid
+pub fn milling(world: &World) -> f32 {
+    let centre = centroid(world);
+    let angular_momenta: Vec<f32> = world
+        .0
+        .iter()
+        .map(|boid| {
+            let radial = normalize_or_zero(&(boid.pos - centre));
+            let heading = heading(boid);
+            radial.x * heading.y - radial.y * heading.x
+        })
+        .collect();
+
+    mean_f32(&angular_momenta).abs()
+}
+
+pub fn mean_nearest_neighbour_distance(World(boids): &World) -> f32 {
+    if boids.len() < 2 {
+        return 0.0;
+    }
+
+    let nearest: Vec<f32> = boids
+        .iter()
+        .enumerate()
+        .map(|(i, boid)| {
+            boids
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| i != *j)
+                .map(|(_, other)| na::distance(&boid.pos, &other.pos))
+                .fold(f32::INFINITY, f32::min)
+        })
+        .collect();
+
+    mean_f32(&nearest)
+}
+
+/// Root mean square distance from the centroid
+pub fn radius_of_gyration(world: &World) -> f32 {
+    let centre = centroid(world);
+    let squared_distances: Vec<f32> = world
+        .0
+        .iter()
+        .map(|boid| na::distance2(&boid.pos, &centre))
+        .collect();
+
+    mean_f32(&squared_distances).sqrt()
+}
+
+impl fmt::Display for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "polarisation {:.3}  milling {:.3}  nearest neighbour {:.4}  gyration {:.4}  speed {:.4} ± {:.4} [{:.4}, {:.4}]",
+            self.polarisation,
+            self.milling,
+            self.mean_nearest_neighbour_distance,
+            self.radius_of_gyration,
+            self.mean_speed,
+            self.speed_std_dev,
+            self.min_speed,
+            self.max_speed
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::*;
+    use std::f32::consts::PI;
+
+    fn ring(count: usize, clockwise: bool) -> World {
+        World(
+            (0..count)
+                .map(|i| {
+                    let angle = 2.0 * PI * i as f32 / count as f32;
+                    let pos = na::vec2(angle.cos(), angle.sin());
+                    let tangent = na::vec2(-pos.y, pos.x);
+                    Entity {
+                        pos,
+                        rot: if clockwise { -tangent } else { tangent },
+                        vel: tangent * 0.5,
+                        ..Default::default()
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_ring_is_milling_and_unpolarised() {
+        for clockwise in &[true, false] {
+            let metrics = measure(&ring(12, *clockwise));
+
+            assert_approx_eq!(metrics.milling, 1.0, 1e-4);
+            assert_approx_eq!(metrics.polarisation, 0.0, 1e-4);
+            assert_approx_eq!(metrics.radius_of_gyration, 1.0, 1e-4);
+            assert_approx_eq!(
+                metrics.mean_nearest_neighbour_distance,
+                2.0 * (PI / 12.0).sin(),
+                1e-4
+            );
+            assert_approx_eq!(metrics.mean_speed, 0.5, 1e-4);
+            assert_approx_eq!(metrics.speed_std_dev, 0.0, 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_aligned_flock_is_polarised() {
+        let mut world = ring(12, false);
+        for boid in world.0.iter_mut() {
+            boid.rot = na::vec2(0.0, 2.0);
+        }
+        let metrics = measure(&world);
+
+        assert_approx_eq!(metrics.polarisation, 1.0, 1e-4);
+        assert_approx_eq!(metrics.milling, 0.0, 1e-4);
+    }
+
+    #[test]
+    fn test_empty_world() {
+        let metrics = measure(&World(vec![]));
+
+        assert_eq!(metrics.polarisation, 0.0);
+        assert_eq!(metrics.max_speed, 0.0);
+    }
+}
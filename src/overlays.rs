@@ -0,0 +1,265 @@
+//! Debug drawings over the boids: velocity, drag and net force arrows, the influence radius,
+//! the field of view, and one arrow per goal. Each can be switched on in the config or toggled
+//! with a key while running.
+
+use crate::boids;
+use crate::boids::goals::{GoalFunction, ResultantGoal};
+use crate::boids::strategies;
+use crate::boids::World;
+use crate::config;
+use crate::geometry;
+use crate::geometry::Model;
+use crate::physics;
+use crate::utilities::{mean, normalize_or_zero};
+use glium::index::PrimitiveType;
+use nalgebra_glm as na;
+
+/// World units per unit of velocity
+const VELOCITY_SCALE: f32 = 0.5;
+/// World units per unit of force
+const FORCE_SCALE: f32 = 2.0;
+/// World units per unit of goal
+const GOAL_SCALE: f32 = 0.1;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Overlay {
+    Velocity,
+    Drag,
+    Force,
+    Influence,
+    FieldOfView,
+    Goals,
+    /// Not an overlay itself, but switches between the selected boid and every boid
+    SelectedOnly,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Overlays {
+    pub velocity: bool,
+    pub drag: bool,
+    pub force: bool,
+    pub influence: bool,
+    pub field_of_view: bool,
+    pub goals: bool,
+    pub selected_only: bool,
+}
+
+impl Overlays {
+    pub fn from_config(config: &config::Config) -> Overlays {
+        Overlays {
+            velocity: config.overlay_velocity,
+            drag: config.overlay_drag,
+            force: config.overlay_force,
+            influence: config.overlay_influence,
+            field_of_view: config.overlay_field_of_view,
+            goals: config.overlay_goals,
+            selected_only: config.overlay_selected_only,
+        }
+    }
+
+    pub fn toggle(&mut self, overlay: Overlay) {
+        let flag = match overlay {
+            Overlay::Velocity => &mut self.velocity,
+            Overlay::Drag => &mut self.drag,
+            Overlay::Force => &mut self.force,
+            Overlay::Influence => &mut self.influence,
+            Overlay::FieldOfView => &mut self.field_of_view,
+            Overlay::Goals => &mut self.goals,
+            Overlay::SelectedOnly => &mut self.selected_only,
+        };
+        *flag = !*flag;
+    }
+
+    /// Overlays for the selected boid, or every boid, plus rings around the selected boid and
+    /// the neighbours it's influenced by
+    pub fn models(
+        &self,
+        world: &World,
+        selected: Option<u32>,
+        config: &config::Config,
+        goal_functions: &[GoalFunction],
+    ) -> Vec<Model> {
+        let selected_index = selected.and_then(|id| world.index_of(id));
+        let indices: Vec<usize> = if self.selected_only {
+            selected_index.into_iter().collect()
+        } else {
+            (0..world.0.len()).collect()
+        };
+
+        let mut models: Vec<Model> = indices
+            .into_iter()
+            .flat_map(|index| self.boid_models(world, index, config, goal_functions))
+            .collect();
+
+        if let Some(index) = selected_index {
+            for id in boids::neighbours(world, index, config) {
+                let neighbour = &world.0[world.index_of(id).unwrap()];
+                models.push(
+                    geometry::circle(0.02, 12)
+                        .map(|v| na::translation2d(&neighbour.pos) * v)
+                        .with_colour(na::vec4(0.9, 0.6, 0.2, 1.0)),
+                );
+            }
+            let pos = world.0[index].pos;
+            models.push(
+                geometry::circle(0.03, 16)
+                    .map(|v| na::translation2d(&pos) * v)
+                    .with_colour(na::vec4(1.0, 1.0, 1.0, 1.0)),
+            );
+        }
+
+        models
+    }
+
+    fn boid_models(
+        &self,
+        world: &World,
+        index: usize,
+        config: &config::Config,
+        goal_functions: &[GoalFunction],
+    ) -> Vec<Model> {
+        let boid = &world.0[index];
+        let mut models = Vec::new();
+        let drag = physics::entity_drag(boid, config.drag_coefficient);
+        let contributions = if self.force || self.goals {
+            boids::goal_contributions(world, index, config, goal_functions)
+        } else {
+            Vec::new()
+        };
+
+        if self.velocity {
+            models.push(
+                geometry::arrow_vector(&(boid.vel * VELOCITY_SCALE))
+                    .with_colour(na::vec4(0.3, 0.9, 0.3, 1.0)),
+            );
+        }
+        if self.drag {
+            models.push(
+                geometry::arrow_vector(&(drag * FORCE_SCALE))
+                    .with_colour(na::vec4(0.9, 0.3, 0.3, 1.0)),
+            );
+        }
+        if self.force {
+            let mut steered = *boid;
+            steered.resultant_force = na::zero();
+            strategies::v1(
+                &mut steered,
+                ResultantGoal(mean(&contributions)),
+                config.max_ang_vel,
+                config.max_force,
+            );
+            models.push(
+                geometry::arrow_vector(&((steered.resultant_force + drag) * FORCE_SCALE))
+                    .with_colour(na::vec4(0.9, 0.8, 0.2, 1.0)),
+            );
+        }
+        if self.goals {
+            for (i, goal) in contributions.iter().enumerate() {
+                models.push(
+                    geometry::arrow_vector(&(goal * GOAL_SCALE))
+                        .with_colour(geometry::group_colour(Some(i))),
+                );
+            }
+        }
+        if self.influence {
+            models.push(
+                geometry::circle(config.influence_radius, 48)
+                    .with_colour(na::vec4(0.4, 0.4, 0.4, 1.0)),
+            );
+        }
+        if self.field_of_view {
+            models.push(
+                cone(&boid.rot, boids::FIELD_OF_VIEW, config.influence_radius)
+                    .with_colour(na::vec4(0.5, 0.5, 0.3, 1.0)),
+            );
+        }
+
+        models
+            .into_iter()
+            .map(|model| model.map(|v| na::translation2d(&boid.pos) * v))
+            .collect()
+    }
+}
+
+/// The outline of the sector `field_of_view` radians wide, centred on `heading`, out to `radius`.
+/// A full circle's two edges meet behind the boid.
+pub fn cone(heading: &na::Vec2, field_of_view: f32, radius: f32) -> Model {
+    const SEGMENTS: u32 = 32;
+
+    let heading = normalize_or_zero(heading);
+    let field_of_view = field_of_view.min(2.0 * std::f32::consts::PI);
+    let start = heading.y.atan2(heading.x) - field_of_view / 2.0;
+    let point = |i: u32| {
+        let angle = start + field_of_view * i as f32 / SEGMENTS as f32;
+        na::vec3(radius * angle.cos(), radius * angle.sin(), 1.0)
+    };
+    let origin = na::vec3(0.0, 0.0, 1.0);
+
+    let mut vertices = vec![origin, point(0), origin, point(SEGMENTS)];
+    vertices.extend((0..SEGMENTS).flat_map(|i| vec![point(i), point(i + 1)]));
+
+    Model(
+        vertices,
+        PrimitiveType::LinesList,
+        geometry::default_colour(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation;
+    use crate::test_utils::test_config;
+    use assert_approx_eq::*;
+
+    #[test]
+    fn test_cone_edges() {
+        let Model(vertices, _, _) = cone(&na::vec2(0.0, 2.0), std::f32::consts::PI / 2.0, 1.0);
+        let edge = vertices[1];
+        let other_edge = vertices[3];
+
+        assert_approx_eq!(na::magnitude(&edge.xy()), 1.0);
+        assert_approx_eq!(edge.x, 0.5f32.sqrt());
+        assert_approx_eq!(other_edge.x, -(0.5f32.sqrt()));
+        assert_approx_eq!(edge.y, other_edge.y);
+
+        // Seeing all round, both edges point straight behind
+        let Model(vertices, _, _) = cone(&na::vec2(0.0, 1.0), boids::FIELD_OF_VIEW, 1.0);
+        assert_approx_eq!(vertices[1].y, -1.0);
+        assert_approx_eq!(vertices[3].y, -1.0);
+    }
+
+    #[test]
+    fn test_overlays_follow_selection() {
+        let config = test_config();
+        let simulation = simulation::new(&config);
+        let goal_functions = simulation::goal_functions(&config, &simulation.environment);
+        let mut overlays = Overlays::from_config(&config);
+        overlays.selected_only = true;
+        overlays.velocity = true;
+        overlays.goals = true;
+
+        let models = |overlays: &Overlays, selected| {
+            overlays
+                .models(&simulation.world, selected, &config, &goal_functions)
+                .len()
+        };
+
+        assert_eq!(models(&overlays, None), 0);
+        // velocity, one arrow per goal, and rings around the selected boid and its neighbours
+        let index = simulation.world.index_of(3).unwrap();
+        let neighbours = boids::neighbours(&simulation.world, index, &config).len();
+        assert!(neighbours > 0);
+        assert_eq!(
+            models(&overlays, Some(3)),
+            1 + goal_functions.len() + neighbours + 1
+        );
+
+        overlays.toggle(Overlay::SelectedOnly);
+        let population = simulation.world.0.len();
+        assert_eq!(
+            models(&overlays, None),
+            population * (1 + goal_functions.len())
+        );
+    }
+}
@@ -1,11 +1,16 @@
 use crate::boids;
 use crate::config;
+use crate::utilities::normalize_or_zero;
 use assert_approx_eq::*;
 use nalgebra_glm as na;
+use serde::{Deserialize, Serialize};
 use std::f32::consts::PI;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct Entity {
+    /// Stays the same for the boid's whole life, unlike its index in the world
+    #[serde(default)]
+    pub id: u32,
     pub pos: na::Vec2,
     pub vel: na::Vec2,
     pub angular_vel: f32,
@@ -13,11 +18,17 @@ pub struct Entity {
     pub mass: f32,
     pub area: f32,
     pub resultant_force: na::Vec2,
+    /// Angle of the wander target on the wander circle, relative to `rot`
+    pub wander_angle: f32,
+    /// Which kind of boid this is, set by scenarios. Only used for colouring.
+    #[serde(default)]
+    pub species: u32,
 }
 
 impl Default for Entity {
     fn default() -> Entity {
         Entity {
+            id: 0,
             pos: na::zero(),
             vel: na::zero(),
             angular_vel: 0.0,
@@ -25,6 +36,8 @@ impl Default for Entity {
             mass: 1.0,
             area: 0.25,
             resultant_force: na::zero(),
+            wander_angle: 0.0,
+            species: 0,
         }
     }
 }
@@ -34,11 +47,7 @@ pub fn add_force(entity: &mut Entity, force: na::Vec2) {
 }
 
 pub fn collinear_force(vector: na::Vec2, magnitude: f32) -> na::Vec2 {
-    if vector == na::zero() {
-        vector
-    } else {
-        magnitude * na::normalize(&vector)
-    }
+    magnitude * normalize_or_zero(&vector)
 }
 
 pub fn step_world(
@@ -52,16 +61,7 @@ pub fn step_world(
 }
 
 pub fn step_entity(entity: &mut Entity, drag_coefficient: f32, delta_time: f32) {
-    let mut dot_c = na::normalize_dot(&entity.vel, &entity.rot);
-    if dot_c < 0.0 {
-        dot_c = 0.0;
-    }
-
-    let drag = drag_force(
-        entity.area + (4.0 * (1.0 - dot_c)),
-        drag_coefficient,
-        entity.vel,
-    );
+    let drag = entity_drag(entity, drag_coefficient);
     add_force(entity, drag);
 
     let acceleration = entity.resultant_force / entity.mass;
@@ -85,6 +85,22 @@ pub fn step_entity(entity: &mut Entity, drag_coefficient: f32, delta_time: f32)
     entity.rot = na::rotate_vec2(&entity.rot, entity.angular_vel * delta_time);
 }
 
+/// Drag on the boid, which grows as it moves more sideways to its heading
+pub fn entity_drag(entity: &Entity, drag_coefficient: f32) -> na::Vec2 {
+    // Undefined without both directions, and drag is zero for a stationary boid anyway
+    let dot_c = if entity.vel == na::zero() || entity.rot == na::zero() {
+        1.0
+    } else {
+        na::normalize_dot(&entity.vel, &entity.rot).max(0.0)
+    };
+
+    drag_force(
+        entity.area + (4.0 * (1.0 - dot_c)),
+        drag_coefficient,
+        entity.vel,
+    )
+}
+
 pub fn integrate(value: na::Vec2, rate_of_change: na::Vec2, delta_time: f32) -> na::Vec2 {
     value + (rate_of_change * delta_time)
 }
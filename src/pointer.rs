@@ -0,0 +1,170 @@
+//! Poking the flock with the mouse. Holding the left button attracts boids to the cursor,
+//! holding the right button repels them, shift-clicking spawns a burst of boids there and
+//! ctrl-clicking selects the boid under the cursor.
+
+use crate::boids::goals::{static_goal_with_falloff, Goal, GoalFunction};
+use crate::config;
+use gl::glutin;
+use glium as gl;
+use nalgebra_glm as na;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Pull {
+    Attract,
+    Repel,
+}
+
+/// A click that does something once, rather than for as long as it's held
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Click {
+    Burst,
+    Select,
+}
+
+#[derive(Debug, Default)]
+pub struct Pointer {
+    pub pull: Option<Pull>,
+}
+
+impl Pointer {
+    pub fn handle_event(&mut self, event: &glutin::Event) -> Option<Click> {
+        let (state, button, modifiers) = match event {
+            glutin::Event::WindowEvent {
+                event:
+                    glutin::WindowEvent::MouseInput {
+                        state,
+                        button,
+                        modifiers,
+                        ..
+                    },
+                ..
+            } => (*state, *button, *modifiers),
+            _ => return None,
+        };
+        let pressed = state == glutin::ElementState::Pressed;
+
+        match button {
+            glutin::MouseButton::Left if pressed && modifiers.shift => return Some(Click::Burst),
+            glutin::MouseButton::Left if pressed && modifiers.ctrl => return Some(Click::Select),
+            glutin::MouseButton::Left => self.release_or(pressed, Pull::Attract),
+            glutin::MouseButton::Right => self.release_or(pressed, Pull::Repel),
+            _ => (),
+        }
+
+        None
+    }
+
+    fn release_or(&mut self, pressed: bool, pull: Pull) {
+        if pressed {
+            self.pull = Some(pull);
+        } else if self.pull == Some(pull) {
+            self.pull = None;
+        }
+    }
+
+    /// The goal to add while a button is held, pulling towards or pushing away from `position`
+    pub fn goal_function(
+        &self,
+        position: na::Vec2,
+        config: &config::Config,
+    ) -> Option<GoalFunction> {
+        let weight = match self.pull? {
+            Pull::Attract => config.pointer_weight,
+            Pull::Repel => -config.pointer_weight,
+        };
+        let falloff = config.pointer_falloff;
+
+        Some(Box::new(move |boid, _| {
+            Goal(static_goal_with_falloff(boid, position, falloff).0 * weight)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::Entity;
+    use crate::test_utils::test_config;
+
+    fn click(
+        button: glutin::MouseButton,
+        pressed: bool,
+        modifiers: glutin::ModifiersState,
+    ) -> glutin::Event {
+        glutin::Event::WindowEvent {
+            window_id: unsafe { glutin::WindowId::dummy() },
+            event: glutin::WindowEvent::MouseInput {
+                device_id: unsafe { glutin::DeviceId::dummy() },
+                state: if pressed {
+                    glutin::ElementState::Pressed
+                } else {
+                    glutin::ElementState::Released
+                },
+                button,
+                modifiers,
+            },
+        }
+    }
+
+    #[test]
+    fn test_buttons() {
+        let none = glutin::ModifiersState::default();
+        let shift = glutin::ModifiersState {
+            shift: true,
+            ..none
+        };
+        let ctrl = glutin::ModifiersState { ctrl: true, ..none };
+        let mut pointer = Pointer::default();
+
+        assert_eq!(
+            pointer.handle_event(&click(glutin::MouseButton::Left, true, none)),
+            None
+        );
+        assert_eq!(pointer.pull, Some(Pull::Attract));
+        pointer.handle_event(&click(glutin::MouseButton::Right, true, none));
+        assert_eq!(pointer.pull, Some(Pull::Repel));
+        // Releasing the button that's no longer pulling leaves the other alone
+        pointer.handle_event(&click(glutin::MouseButton::Left, false, none));
+        assert_eq!(pointer.pull, Some(Pull::Repel));
+        pointer.handle_event(&click(glutin::MouseButton::Right, false, none));
+        assert_eq!(pointer.pull, None);
+
+        assert_eq!(
+            pointer.handle_event(&click(glutin::MouseButton::Left, true, shift)),
+            Some(Click::Burst)
+        );
+        assert_eq!(
+            pointer.handle_event(&click(glutin::MouseButton::Left, true, ctrl)),
+            Some(Click::Select)
+        );
+        assert_eq!(pointer.pull, None);
+    }
+
+    #[test]
+    fn test_attract_and_repel() {
+        let config = test_config();
+        let boid = Entity {
+            pos: na::vec2(0.1, 0.0),
+            ..Default::default()
+        };
+        let far = Entity {
+            pos: na::vec2(0.1 + config.pointer_falloff, 0.0),
+            ..Default::default()
+        };
+        let goal = |pull| {
+            let pointer = Pointer { pull: Some(pull) };
+            let function = pointer.goal_function(na::zero(), &config).unwrap();
+            (function(&boid, &[]).0, function(&far, &[]).0)
+        };
+
+        let (attract, attract_far) = goal(Pull::Attract);
+        let (repel, repel_far) = goal(Pull::Repel);
+        assert!(attract.x < 0.0 && repel.x > 0.0);
+        assert_eq!(attract, -repel);
+        assert_eq!(attract_far, na::zero());
+        assert_eq!(repel_far, na::zero());
+        assert!(Pointer::default()
+            .goal_function(na::zero(), &config)
+            .is_none());
+    }
+}
@@ -0,0 +1,425 @@
+//! Trajectories are stored as a header followed by one frame per recorded step, all little endian:
+//!
+//! - header: `MAGIC`, crate version (u32 length + UTF-8), seed (u64), config (u32 length + TOML)
+//! - frame: step (u64), boid count (u32), then for each boid
+//!   id (u32), species (u32), pos.x, pos.y, vel.x, vel.y, rot.x, rot.y, angular_vel, area (f32)
+//!
+//! Files starting with `MAGIC_V2` predate species, and their boids are read back as species 0.
+//! Files starting with `MAGIC_V1` also predate ids, and their boids are numbered by index.
+//!
+//! Frames are written as they happen and read back one at a time, so long runs never have to fit in memory.
+//! `Frames` indexes where each frame starts so that they can also be read in any order.
+
+use crate::boids::World;
+use crate::config;
+use crate::physics::Entity;
+use nalgebra_glm as na;
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+pub const MAGIC: &[u8; 8] = b"BOIDTRJ3";
+pub const MAGIC_V2: &[u8; 8] = b"BOIDTRJ2";
+pub const MAGIC_V1: &[u8; 8] = b"BOIDTRJ1";
+
+pub struct Header {
+    /// Version of the crate that recorded the trajectory
+    pub version: String,
+    pub seed: u64,
+    pub config: config::Config,
+}
+
+pub struct Frame {
+    pub step: u64,
+    pub world: World,
+}
+
+pub struct Recorder<W: Write> {
+    writer: W,
+    every: u64,
+}
+
+pub fn create(
+    path: &Path,
+    config: &config::Config,
+    every: u64,
+) -> io::Result<Recorder<BufWriter<File>>> {
+    Recorder::new(BufWriter::new(File::create(path)?), config, every)
+}
+
+impl<W: Write> Recorder<W> {
+    /// Writes the header straight away; frames are only kept for steps divisible by `every`
+    pub fn new(mut writer: W, config: &config::Config, every: u64) -> io::Result<Recorder<W>> {
+        let config_toml = toml::to_string(config).map_err(invalid_data)?;
+
+        writer.write_all(MAGIC)?;
+        write_string(&mut writer, env!("CARGO_PKG_VERSION"))?;
+        writer.write_all(&config.seed.to_le_bytes())?;
+        write_string(&mut writer, &config_toml)?;
+
+        Ok(Recorder {
+            writer,
+            every: every.max(1),
+        })
+    }
+
+    pub fn record(&mut self, step: u64, World(boids): &World) -> io::Result<()> {
+        if !step.is_multiple_of(self.every) {
+            return Ok(());
+        }
+
+        self.writer.write_all(&step.to_le_bytes())?;
+        self.writer.write_all(&(boids.len() as u32).to_le_bytes())?;
+        for boid in boids {
+            self.writer.write_all(&boid.id.to_le_bytes())?;
+            self.writer.write_all(&boid.species.to_le_bytes())?;
+            for value in &[
+                boid.pos.x,
+                boid.pos.y,
+                boid.vel.x,
+                boid.vel.y,
+                boid.rot.x,
+                boid.rot.y,
+                boid.angular_vel,
+                boid.area,
+            ] {
+                self.writer.write_all(&value.to_le_bytes())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+pub struct Reader<R: Read> {
+    reader: R,
+    pub header: Header,
+    /// Which of the formats in the module docs the file is in, from 1
+    format: u8,
+}
+
+pub fn open(path: &Path) -> io::Result<Reader<BufReader<File>>> {
+    Reader::new(BufReader::new(File::open(path)?))
+}
+
+impl<R: Read> Reader<R> {
+    pub fn new(mut reader: R) -> io::Result<Reader<R>> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        let format = match &magic {
+            MAGIC => 3,
+            MAGIC_V2 => 2,
+            MAGIC_V1 => 1,
+            _ => return Err(invalid_data("not a boids trajectory")),
+        };
+
+        let version = read_string(&mut reader)?;
+        let seed = read_u64(&mut reader)?;
+        let config = toml::from_str(&read_string(&mut reader)?).map_err(invalid_data)?;
+
+        Ok(Reader {
+            reader,
+            header: Header {
+                version,
+                seed,
+                config,
+            },
+            format,
+        })
+    }
+
+    fn has_ids(&self) -> bool {
+        self.format >= 2
+    }
+
+    fn has_species(&self) -> bool {
+        self.format >= 3
+    }
+
+    /// Bytes taken by each boid in a frame
+    fn boid_bytes(&self) -> u64 {
+        let id_bytes = if self.has_ids() { 4 } else { 0 };
+        let species_bytes = if self.has_species() { 4 } else { 0 };
+        id_bytes + species_bytes + 8 * 4
+    }
+
+    fn read_frame(&mut self, step: u64) -> io::Result<Frame> {
+        let count = read_u32(&mut self.reader)?;
+        let mut boids = Vec::with_capacity(count as usize);
+
+        for index in 0..count {
+            let id = if self.has_ids() {
+                read_u32(&mut self.reader)?
+            } else {
+                index
+            };
+            let species = if self.has_species() {
+                read_u32(&mut self.reader)?
+            } else {
+                0
+            };
+            let mut values = [0.0; 8];
+            for value in values.iter_mut() {
+                *value = read_f32(&mut self.reader)?;
+            }
+
+            boids.push(Entity {
+                id,
+                species,
+                pos: na::vec2(values[0], values[1]),
+                vel: na::vec2(values[2], values[3]),
+                rot: na::vec2(values[4], values[5]),
+                angular_vel: values[6],
+                area: values[7],
+                ..Default::default()
+            });
+        }
+
+        Ok(Frame {
+            step,
+            world: World(boids),
+        })
+    }
+}
+
+impl<R: Read> Iterator for Reader<R> {
+    type Item = io::Result<Frame>;
+
+    fn next(&mut self) -> Option<io::Result<Frame>> {
+        let mut step = [0; 8];
+        match self.reader.read_exact(&mut step) {
+            Ok(()) => Some(self.read_frame(u64::from_le_bytes(step))),
+            Err(ref error) if error.kind() == io::ErrorKind::UnexpectedEof => None,
+            Err(error) => Some(Err(error)),
+        }
+    }
+}
+
+/// Reads a recording's frames in any order. Only each frame's step and position in the file are
+/// kept in memory.
+pub struct Frames<R: Read + Seek> {
+    reader: Reader<R>,
+    /// Step of each frame and the offset of its boid count, in the order they were recorded
+    index: Vec<(u64, u64)>,
+}
+
+pub fn open_frames(path: &Path) -> io::Result<Frames<BufReader<File>>> {
+    Frames::new(open(path)?)
+}
+
+impl<R: Read + Seek> Frames<R> {
+    /// Skips through the file once to find every frame. A frame cut short at the end, as left by
+    /// a run that was killed, is left out.
+    pub fn new(mut reader: Reader<R>) -> io::Result<Frames<R>> {
+        let start = reader.reader.stream_position()?;
+        let end = reader.reader.seek(SeekFrom::End(0))?;
+        let mut position = reader.reader.seek(SeekFrom::Start(start))?;
+        let mut index = Vec::new();
+
+        while position + 12 <= end {
+            let step = read_u64(&mut reader.reader)?;
+            let count = read_u32(&mut reader.reader)?;
+            let next = position + 12 + u64::from(count) * reader.boid_bytes();
+            if next > end {
+                break;
+            }
+
+            index.push((step, position + 8));
+            position = reader.reader.seek(SeekFrom::Start(next))?;
+        }
+
+        Ok(Frames { reader, index })
+    }
+
+    pub fn header(&self) -> &Header {
+        &self.reader.header
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    pub fn steps(&self) -> Vec<u64> {
+        self.index.iter().map(|&(step, _)| step).collect()
+    }
+
+    pub fn read(&mut self, frame: usize) -> io::Result<Frame> {
+        let (step, offset) = self.index[frame];
+        self.reader.reader.seek(SeekFrom::Start(offset))?;
+        self.reader.read_frame(step)
+    }
+}
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+fn write_string(writer: &mut impl Write, string: &str) -> io::Result<()> {
+    writer.write_all(&(string.len() as u32).to_le_bytes())?;
+    writer.write_all(string.as_bytes())
+}
+
+fn read_string(reader: &mut impl Read) -> io::Result<String> {
+    let mut bytes = vec![0; read_u32(reader)? as usize];
+    reader.read_exact(&mut bytes)?;
+    String::from_utf8(bytes).map_err(invalid_data)
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_f32(reader: &mut impl Read) -> io::Result<f32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(f32::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation;
+    use crate::test_utils::test_config;
+
+    #[test]
+    fn test_round_trip() {
+        let config = test_config();
+        let mut simulation = simulation::new(&config);
+        let goal_functions = simulation::goal_functions(&config, &simulation.environment);
+        for (index, boid) in simulation.world.0.iter_mut().enumerate() {
+            boid.species = index as u32 % 3;
+        }
+
+        let mut bytes = Vec::new();
+        let mut recorder = Recorder::new(&mut bytes, &config, 3).unwrap();
+        let mut recorded = Vec::new();
+        for _ in 0..10 {
+            simulation.step(&config, &goal_functions, simulation::DELTA_TIME);
+            recorder.record(simulation.step, &simulation.world).unwrap();
+            if simulation.step.is_multiple_of(3) {
+                recorded.push(simulation.world.0.clone());
+            }
+        }
+
+        let reader = Reader::new(&bytes[..]).unwrap();
+        assert_eq!(reader.header.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(reader.header.seed, config.seed);
+        assert_eq!(reader.header.config.population, config.population);
+
+        let frames: Vec<Frame> = reader.map(Result::unwrap).collect();
+        assert_eq!(
+            frames.iter().map(|frame| frame.step).collect::<Vec<u64>>(),
+            vec![3, 6, 9]
+        );
+        for (frame, boids) in frames.iter().zip(recorded) {
+            for (read, written) in frame.world.0.iter().zip(boids) {
+                assert_eq!(read.id, written.id);
+                assert_eq!(read.species, written.species);
+                assert_eq!(read.pos, written.pos);
+                assert_eq!(read.vel, written.vel);
+                assert_eq!(read.rot, written.rot);
+                assert_eq!(read.angular_vel, written.angular_vel);
+                assert_eq!(read.area, written.area);
+            }
+        }
+    }
+
+    #[test]
+    fn test_reads_version_1() {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC_V1);
+        write_string(&mut bytes, "0.1.0").unwrap();
+        bytes.extend_from_slice(&1u64.to_le_bytes());
+        write_string(&mut bytes, &toml::to_string(&test_config()).unwrap()).unwrap();
+        bytes.extend_from_slice(&5u64.to_le_bytes());
+        bytes.extend_from_slice(&2u32.to_le_bytes());
+        for value in 0..16 {
+            bytes.extend_from_slice(&(value as f32).to_le_bytes());
+        }
+
+        let frames: Vec<Frame> = Reader::new(&bytes[..])
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(frames.len(), 1);
+        let boids = &frames[0].world.0;
+        assert_eq!((boids[0].id, boids[1].id), (0, 1));
+        assert_eq!(boids[1].pos, na::vec2(8.0, 9.0));
+    }
+
+    #[test]
+    fn test_reads_version_2() {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC_V2);
+        write_string(&mut bytes, "0.1.0").unwrap();
+        bytes.extend_from_slice(&1u64.to_le_bytes());
+        write_string(&mut bytes, &toml::to_string(&test_config()).unwrap()).unwrap();
+        bytes.extend_from_slice(&5u64.to_le_bytes());
+        bytes.extend_from_slice(&2u32.to_le_bytes());
+        for id in &[4u32, 9] {
+            bytes.extend_from_slice(&id.to_le_bytes());
+            for value in 0..8 {
+                bytes.extend_from_slice(&(value as f32).to_le_bytes());
+            }
+        }
+
+        let mut frames = Frames::new(Reader::new(io::Cursor::new(bytes)).unwrap()).unwrap();
+        assert_eq!(frames.len(), 1);
+        let boids = frames.read(0).unwrap().world.0;
+        assert_eq!((boids[0].id, boids[1].id), (4, 9));
+        assert_eq!((boids[0].species, boids[1].species), (0, 0));
+        assert_eq!(boids[1].area, 7.0);
+    }
+
+    #[test]
+    fn test_frames_read_in_any_order() {
+        let config = test_config();
+        let mut simulation = simulation::new(&config);
+        let goal_functions = simulation::goal_functions(&config, &simulation.environment);
+
+        let mut bytes = Vec::new();
+        let mut recorder = Recorder::new(&mut bytes, &config, 1).unwrap();
+        let mut recorded = Vec::new();
+        for _ in 0..5 {
+            simulation.step(&config, &goal_functions, simulation::DELTA_TIME);
+            recorder.record(simulation.step, &simulation.world).unwrap();
+            recorded.push(simulation.world.0.clone());
+        }
+        // Half a frame, as if the recorder was killed mid-write
+        bytes.extend_from_slice(&6u64.to_le_bytes());
+        bytes.extend_from_slice(&3u32.to_le_bytes());
+        bytes.extend_from_slice(&[0; 10]);
+
+        let mut frames = Frames::new(Reader::new(io::Cursor::new(bytes)).unwrap()).unwrap();
+        assert_eq!(frames.steps(), vec![1, 2, 3, 4, 5]);
+        for &index in &[3, 0, 4, 1] {
+            let frame = frames.read(index).unwrap();
+            assert_eq!(frame.step, index as u64 + 1);
+            let positions: Vec<na::Vec2> = frame.world.0.iter().map(|boid| boid.pos).collect();
+            let expected: Vec<na::Vec2> = recorded[index].iter().map(|boid| boid.pos).collect();
+            assert_eq!(positions, expected);
+        }
+    }
+
+    #[test]
+    fn test_rejects_other_files() {
+        assert!(Reader::new(&b"not a trajectory"[..]).is_err());
+    }
+}
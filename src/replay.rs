@@ -0,0 +1,261 @@
+use crate::camera::Camera;
+use crate::draw::Drawer;
+use crate::geometry;
+use crate::input;
+use crate::input::Action;
+use crate::overlays::Overlays;
+use crate::recording;
+use crate::scenario::Environment;
+use crate::selection;
+use crate::simulation;
+use crate::simulation::DELTA_TIME;
+use crate::utilities::{key_pressed, window_closed};
+use gl::glutin;
+use gl::Surface;
+use glium as gl;
+use std::fs::File;
+use std::io;
+use std::io::BufReader;
+use std::path::Path;
+use std::time::Instant;
+
+/// Seconds jumped by a seek
+const SEEK_SECONDS: f32 = 5.0;
+
+/// Plays back recorded frames at the speed they were simulated, scaled by `speed`.
+/// Only keeps track of which frame is current; the frames themselves are read as they're needed.
+pub struct Player {
+    /// Step of every frame, in order
+    steps: Vec<u64>,
+    /// Playback position, in simulation steps
+    time: f64,
+    index: usize,
+    pub playing: bool,
+    pub speed: f32,
+}
+
+impl Player {
+    /// `steps` must be non-empty and in order
+    pub fn new(steps: Vec<u64>) -> Player {
+        let time = steps[0] as f64;
+
+        Player {
+            steps,
+            time,
+            index: 0,
+            playing: true,
+            speed: 1.0,
+        }
+    }
+
+    /// Index of the frame to show
+    pub fn current(&self) -> usize {
+        self.index
+    }
+
+    fn first_step(&self) -> f64 {
+        self.steps[0] as f64
+    }
+
+    fn last_step(&self) -> f64 {
+        self.steps[self.steps.len() - 1] as f64
+    }
+
+    fn seek_to(&mut self, time: f64) {
+        self.time = time.max(self.first_step()).min(self.last_step());
+        let time = self.time;
+        self.index = self.steps.partition_point(|&step| step as f64 <= time) - 1;
+    }
+
+    fn step_frames(&mut self, frames: isize) {
+        self.playing = false;
+        let index = (self.index as isize + frames).clamp(0, self.steps.len() as isize - 1);
+        self.seek_to(self.steps[index as usize] as f64);
+    }
+
+    /// Moves playback on by `seconds` of real time, stopping at the last frame
+    pub fn advance(&mut self, seconds: f32) {
+        if !self.playing {
+            return;
+        }
+
+        self.seek_to(self.time + f64::from(seconds * self.speed / DELTA_TIME));
+        if self.time >= self.last_step() {
+            self.playing = false;
+        }
+    }
+
+    /// Pause plays and pauses, faster and slower change speed, step and step back move one frame,
+    /// and the seek and jump actions move further. Returns whether the action was the player's.
+    pub fn handle_action(&mut self, action: Action) -> bool {
+        let seek_steps = f64::from(SEEK_SECONDS / DELTA_TIME);
+
+        match action {
+            Action::Pause => {
+                if !self.playing && self.time >= self.last_step() {
+                    self.seek_to(self.first_step());
+                }
+                self.playing = !self.playing;
+            }
+            Action::SeekBackward => self.seek_to(self.time - seek_steps),
+            Action::SeekForward => self.seek_to(self.time + seek_steps),
+            Action::JumpToStart => self.seek_to(self.first_step()),
+            Action::JumpToEnd => self.seek_to(self.last_step()),
+            Action::Faster => self.speed *= 2.0,
+            Action::Slower => self.speed /= 2.0,
+            Action::StepBack => self.step_frames(-1),
+            Action::Step => self.step_frames(1),
+            _ => return false,
+        }
+
+        true
+    }
+
+    /// `step` is that of the frame being shown
+    pub fn status(&self, step: u64) -> String {
+        format!(
+            "frame {}/{}  step {}  speed {}x  {}",
+            self.index + 1,
+            self.steps.len(),
+            step,
+            self.speed,
+            if self.playing { "playing" } else { "paused" }
+        )
+    }
+}
+
+/// Indexes the recording's frames without reading them
+pub fn load(path: &Path) -> io::Result<recording::Frames<BufReader<File>>> {
+    let frames = recording::open_frames(path)?;
+    if frames.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "the recording has no frames",
+        ));
+    }
+
+    Ok(frames)
+}
+
+/// Draws recorded frames through the same models as the live simulation, instead of stepping physics.
+/// Boids are coloured following the config they were recorded with, and the overlay and camera
+/// keys from `keys` work as they do live. Recordings don't keep the environment, so goal overlays
+/// leave it out.
+pub fn run(
+    path: &Path,
+    keys: &input::Keys,
+    drawer: &mut Drawer,
+    events_loop: &mut glutin::EventsLoop,
+    camera: &mut Camera,
+) {
+    let mut frames = load(path).expect("Couldn't read the recording");
+    println!(
+        "replaying {} frames recorded by version {} with seed {}",
+        frames.len(),
+        frames.header().version,
+        frames.header().seed
+    );
+
+    let goal_functions =
+        simulation::goal_functions(&frames.header().config, &Environment::default());
+    let mut overlays = Overlays::from_config(&frames.header().config);
+    let mut selected = None;
+    let mut player = Player::new(frames.steps());
+    let mut shown = frames.read(0).expect("Couldn't read the first frame");
+    let mut shown_index = 0;
+    let mut delta = Instant::now();
+
+    let mut closed = false;
+    while !closed {
+        let mut changed = false;
+        events_loop.poll_events(|e| {
+            if window_closed(&e) {
+                closed = true
+            }
+            if let Some(action) = key_pressed(&e).and_then(|key| input::action(key, keys)) {
+                changed |= player.handle_action(action);
+                match action {
+                    Action::ToggleOverlay(overlay) => overlays.toggle(overlay),
+                    Action::CycleSelection => selected = selection::next(&shown.world, selected),
+                    _ => camera.handle_action(action, &shown.world, selected),
+                }
+            }
+            camera.handle_event(&e);
+        });
+
+        player.advance(delta.elapsed().as_secs_f32());
+        delta = Instant::now();
+
+        if player.current() != shown_index {
+            shown_index = player.current();
+            shown = frames.read(shown_index).expect("Couldn't read a frame");
+        }
+        if changed {
+            println!("{}", player.status(shown.step));
+        }
+
+        let world = &shown.world;
+        let config = &frames.header().config;
+        camera.update(world);
+        let colours = geometry::boid_colours(world, config);
+        drawer.add_boids(world, &colours);
+        for model in overlays.models(world, selected, config, &goal_functions) {
+            drawer.add_model(model);
+        }
+
+        let mut frame = drawer.display.draw();
+        frame.clear_color(0.0, 0.0, 0.0, 1.0);
+        drawer.draw(&mut frame, &camera.view_projection());
+        frame.finish().unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player() -> Player {
+        Player::new((0..10).map(|i| i * 10).collect())
+    }
+
+    fn step(player: &Player) -> u64 {
+        player.steps[player.current()]
+    }
+
+    #[test]
+    fn test_playback_stops_at_end() {
+        let mut player = player();
+
+        player.advance(DELTA_TIME * 25.0);
+        assert_eq!(step(&player), 20);
+
+        player.advance(DELTA_TIME * 1000.0);
+        assert_eq!(step(&player), 90);
+        assert!(!player.playing);
+    }
+
+    #[test]
+    fn test_controls() {
+        let mut player = player();
+
+        player.handle_action(Action::Step);
+        assert!(!player.playing);
+        assert_eq!(step(&player), 10);
+
+        player.handle_action(Action::StepBack);
+        player.handle_action(Action::StepBack);
+        assert_eq!(step(&player), 0);
+
+        player.handle_action(Action::JumpToEnd);
+        assert_eq!(step(&player), 90);
+
+        assert!(!player.handle_action(Action::ToggleTrails));
+        player.handle_action(Action::Faster);
+        player.handle_action(Action::Pause);
+        assert!(player.playing);
+        assert_eq!(step(&player), 0);
+
+        player.advance(DELTA_TIME * 10.0);
+        assert_eq!(step(&player), 20);
+    }
+}
@@ -0,0 +1,346 @@
+//! Scenario files describe the initial conditions of an experiment in TOML:
+//! where boids start and which way they face, obstacles, goal points, and optionally
+//! the seed and the whole config, so that one file reproduces an experiment.
+
+use crate::boids::World;
+use crate::config;
+use crate::generation;
+use crate::physics::Entity;
+use crate::simulation;
+use nalgebra_glm as na;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
+use std::fs;
+use std::path::Path;
+
+#[derive(Deserialize)]
+pub struct Scenario {
+    /// Replaces the config's seed
+    pub seed: Option<u64>,
+    /// Replaces boids.toml
+    pub config: Option<config::Config>,
+    #[serde(default)]
+    pub boids: Vec<Placement>,
+    #[serde(flatten)]
+    pub environment: Environment,
+}
+
+/// Where a set of boids starts, and what kind of boid they are
+#[derive(Deserialize)]
+pub struct Placement {
+    #[serde(default)]
+    pub species: u32,
+    #[serde(flatten)]
+    pub shape: Shape,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "shape", rename_all = "snake_case")]
+pub enum Shape {
+    /// Uniformly spread over a disc
+    Cluster {
+        centre: na::Vec2,
+        radius: f32,
+        count: u32,
+        #[serde(default)]
+        heading: Heading,
+    },
+    /// Evenly spaced around a circle
+    Ring {
+        centre: na::Vec2,
+        radius: f32,
+        count: u32,
+        #[serde(default)]
+        heading: Heading,
+    },
+    /// Evenly spaced from `start` to `end`
+    Line {
+        start: na::Vec2,
+        end: na::Vec2,
+        count: u32,
+        #[serde(default)]
+        heading: Heading,
+    },
+    /// `columns` by `rows` boids, filling the rectangle from `min` to `max`
+    Grid {
+        min: na::Vec2,
+        max: na::Vec2,
+        columns: u32,
+        rows: u32,
+        #[serde(default)]
+        heading: Heading,
+    },
+    /// Boids at exactly these positions, facing `headings` in the same order if given
+    Explicit {
+        positions: Vec<na::Vec2>,
+        #[serde(default)]
+        headings: Vec<f32>,
+        #[serde(default)]
+        heading: Heading,
+    },
+}
+
+/// Which way boids in a placement start facing.
+/// Angles are counter-clockwise from the x axis, in radians.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Heading {
+    #[default]
+    Random,
+    Aligned(f32),
+    /// Counter-clockwise around the centre of the placement
+    Vortex,
+}
+
+/// Fixed features of the arena that boids react to
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Environment {
+    #[serde(default)]
+    pub obstacles: Vec<Obstacle>,
+    #[serde(default)]
+    pub goals: Vec<GoalPoint>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Obstacle {
+    pub centre: na::Vec2,
+    pub radius: f32,
+    /// Distance beyond the edge at which boids start to steer away
+    #[serde(default = "default_falloff")]
+    pub falloff: f32,
+    #[serde(default = "default_obstacle_weight")]
+    pub weight: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct GoalPoint {
+    pub position: na::Vec2,
+    #[serde(default = "default_goal_weight")]
+    pub weight: f32,
+}
+
+fn default_falloff() -> f32 {
+    0.2
+}
+
+fn default_obstacle_weight() -> f32 {
+    10.0
+}
+
+fn default_goal_weight() -> f32 {
+    1.0
+}
+
+pub fn read(path: &Path) -> Result<Scenario, String> {
+    let contents = fs::read_to_string(path).map_err(|error| error.to_string())?;
+    toml::from_str(&contents).map_err(|error| error.to_string())
+}
+
+/// The config the scenario runs with, given the one from boids.toml. Applied again each time
+/// boids.toml is reloaded, so the scenario's own config and seed always win.
+pub fn config(scenario: &Scenario, config: config::Config) -> config::Config {
+    let mut config = scenario.config.clone().unwrap_or(config);
+    if let Some(seed) = scenario.seed {
+        config.seed = seed;
+    }
+
+    config
+}
+
+pub fn simulation(scenario: &Scenario, config: &config::Config) -> simulation::Simulation {
+    let mut rng = generation::seeded_rng(config.seed);
+    let world = World(
+        scenario
+            .boids
+            .iter()
+            .flat_map(|placement| place(placement, &mut rng))
+            .collect(),
+    );
+
+    simulation::with_world(config, world, rng, scenario.environment.clone())
+}
+
+fn place(placement: &Placement, rng: &mut impl Rng) -> Vec<Entity> {
+    let (positions, heading): (Vec<na::Vec2>, Heading) = match &placement.shape {
+        Shape::Cluster {
+            centre,
+            radius,
+            count,
+            heading,
+        } => (
+            (0..*count)
+                .map(|_| {
+                    let distance = *radius * rng.gen_range(0.0f32, 1.0).sqrt();
+                    let angle = rng.gen_range(0.0, 2.0 * PI);
+                    centre + distance * na::vec2(angle.cos(), angle.sin())
+                })
+                .collect(),
+            *heading,
+        ),
+        Shape::Ring {
+            centre,
+            radius,
+            count,
+            heading,
+        } => (
+            (0..*count)
+                .map(|i| {
+                    let angle = 2.0 * PI * i as f32 / *count as f32;
+                    centre + *radius * na::vec2(angle.cos(), angle.sin())
+                })
+                .collect(),
+            *heading,
+        ),
+        Shape::Line {
+            start,
+            end,
+            count,
+            heading,
+        } => (
+            (0..*count)
+                .map(|i| {
+                    let t = if *count > 1 {
+                        i as f32 / (*count - 1) as f32
+                    } else {
+                        0.5
+                    };
+                    start + (end - start) * t
+                })
+                .collect(),
+            *heading,
+        ),
+        Shape::Grid {
+            min,
+            max,
+            columns,
+            rows,
+            heading,
+        } => {
+            let spacing = na::vec2(
+                (max.x - min.x) / (*columns).max(1) as f32,
+                (max.y - min.y) / (*rows).max(1) as f32,
+            );
+            (
+                (0..*rows)
+                    .flat_map(|row| {
+                        (0..*columns).map(move |column| {
+                            min + na::vec2(
+                                (column as f32 + 0.5) * spacing.x,
+                                (row as f32 + 0.5) * spacing.y,
+                            )
+                        })
+                    })
+                    .collect(),
+                *heading,
+            )
+        }
+        Shape::Explicit {
+            positions, heading, ..
+        } => (positions.clone(), *heading),
+    };
+    let explicit_headings: &[f32] = match &placement.shape {
+        Shape::Explicit { headings, .. } => headings,
+        _ => &[],
+    };
+
+    let centre = crate::utilities::mean(&positions);
+    positions
+        .iter()
+        .enumerate()
+        .map(|(i, &pos)| {
+            let angle = match heading {
+                _ if i < explicit_headings.len() => explicit_headings[i],
+                Heading::Random => rng.gen_range(0.0, 2.0 * PI),
+                Heading::Aligned(angle) => angle,
+                Heading::Vortex => {
+                    let radial = pos - centre;
+                    radial.y.atan2(radial.x) + PI / 2.0
+                }
+            };
+
+            Entity {
+                pos,
+                rot: na::vec2(angle.cos(), angle.sin()),
+                wander_angle: rng.gen_range(-PI, PI),
+                species: placement.species,
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::test_config;
+    use assert_approx_eq::*;
+
+    #[test]
+    fn test_example_scenario() {
+        let scenario: Scenario = toml::from_str(include_str!("../scenarios/example.toml")).unwrap();
+        let config = config(&scenario, test_config());
+        let simulation = simulation(&scenario, &config);
+
+        assert_eq!(config.seed, 42);
+        assert_eq!(simulation.world.0.len(), 30 + 12 + 5 + 16 + 2);
+        assert_eq!(simulation.environment.obstacles.len(), 1);
+        assert_eq!(simulation.environment.goals.len(), 1);
+        assert_approx_eq!(simulation.environment.obstacles[0].falloff, 0.2);
+    }
+
+    #[test]
+    fn test_scenario_config_survives_a_reload() {
+        let mut scenario: Scenario =
+            toml::from_str(include_str!("../scenarios/example.toml")).unwrap();
+        let reloaded = config::Config {
+            seed: 7,
+            population: 3,
+            ..test_config()
+        };
+        assert_eq!(config(&scenario, reloaded.clone()).seed, 42);
+
+        let own = config::Config {
+            population: 11,
+            ..test_config()
+        };
+        scenario.config = Some(own);
+        let config = config(&scenario, reloaded);
+        assert_eq!((config.seed, config.population), (42, 11));
+    }
+
+    #[test]
+    fn test_vortex_ring_circles_its_centre() {
+        let placement = Placement {
+            species: 2,
+            shape: Shape::Ring {
+                centre: na::vec2(0.5, 0.5),
+                radius: 0.25,
+                count: 8,
+                heading: Heading::Vortex,
+            },
+        };
+        let boids = place(&placement, &mut generation::seeded_rng(0));
+
+        for boid in &boids {
+            let radial = boid.pos - na::vec2(0.5, 0.5);
+            assert_approx_eq!(na::magnitude(&radial), 0.25, 1e-5);
+            assert_approx_eq!(na::dot(&radial, &boid.rot), 0.0, 1e-5);
+            assert!(radial.x * boid.rot.y - radial.y * boid.rot.x > 0.0);
+            assert_eq!(boid.species, 2);
+        }
+    }
+
+    #[test]
+    fn test_same_seed_same_world() {
+        let scenario: Scenario = toml::from_str(include_str!("../scenarios/example.toml")).unwrap();
+        let config = test_config();
+        let a = simulation(&scenario, &config);
+        let b = simulation(&scenario, &config);
+
+        for (a, b) in a.world.0.iter().zip(&b.world.0) {
+            assert_eq!(a.pos, b.pos);
+            assert_eq!(a.rot, b.rot);
+        }
+    }
+}
@@ -0,0 +1,76 @@
+//! Which boid the overlays and the inspector are about, by id so that it survives steps.
+
+use crate::boids::World;
+use crate::camera::nearest_boid;
+use nalgebra_glm as na;
+
+/// How close a click must be to a boid to select it, as a fraction of the camera's half height
+pub const PICK_RADIUS: f32 = 0.03;
+
+/// The boid with the next id after `selected`, wrapping round to the lowest
+pub fn next(World(boids): &World, selected: Option<u32>) -> Option<u32> {
+    let after = |id: u32| selected.is_none_or(|selected| id > selected);
+
+    boids
+        .iter()
+        .map(|boid| boid.id)
+        .filter(|&id| after(id))
+        .min()
+        .or_else(|| boids.iter().map(|boid| boid.id).min())
+}
+
+/// The boid nearest `position`, if it's within `radius` of it
+pub fn at(world: &World, position: na::Vec2, radius: f32) -> Option<u32> {
+    let id = nearest_boid(world, position)?;
+    let boid = &world.0[world.index_of(id)?];
+
+    if na::distance(&boid.pos, &position) <= radius {
+        Some(id)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::Entity;
+
+    #[test]
+    fn test_next_wraps_round() {
+        let world = World(
+            [5, 2, 9]
+                .iter()
+                .map(|&id| Entity {
+                    id,
+                    ..Default::default()
+                })
+                .collect(),
+        );
+
+        assert_eq!(next(&world, None), Some(2));
+        assert_eq!(next(&world, Some(2)), Some(5));
+        assert_eq!(next(&world, Some(5)), Some(9));
+        assert_eq!(next(&world, Some(9)), Some(2));
+        assert_eq!(next(&world, Some(3)), Some(5));
+        assert_eq!(next(&World(vec![]), Some(3)), None);
+    }
+
+    #[test]
+    fn test_at_picks_nearest_within_radius() {
+        let world = World(
+            [(4, 0.0), (7, 0.1)]
+                .iter()
+                .map(|&(id, x)| Entity {
+                    id,
+                    pos: na::vec2(x, 0.0),
+                    ..Default::default()
+                })
+                .collect(),
+        );
+
+        assert_eq!(at(&world, na::vec2(0.08, 0.01), 0.05), Some(7));
+        assert_eq!(at(&world, na::vec2(0.02, 0.0), 0.05), Some(4));
+        assert_eq!(at(&world, na::vec2(0.5, 0.5), 0.05), None);
+    }
+}
@@ -0,0 +1,338 @@
+use crate::boids;
+use crate::boids::goals::{GoalFunction, InfluenceRadius};
+use crate::boids::World;
+use crate::config;
+use crate::generation;
+use crate::invariants;
+use crate::physics;
+use crate::scenario::Environment;
+use nalgebra_glm as na;
+use serde::{Deserialize, Serialize};
+
+/// Seconds of simulated time per step
+pub const DELTA_TIME: f32 = 0.016;
+
+/// The update rule that moves the world forward each step
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Rule {
+    /// Goals resolved by `strategies::v1` into forces, integrated by `physics`
+    Boids,
+    Vicsek,
+    CuckerSmale,
+}
+
+/// Names of the goals from `goal_functions`, in the same order
+pub fn goal_names(environment: &Environment) -> Vec<&'static str> {
+    let mut names = vec!["cohesion", "separation", "alignment", "wander", "bound"];
+    if !environment.obstacles.is_empty() {
+        names.push("obstacles");
+    }
+    if !environment.goals.is_empty() {
+        names.push("goal points");
+    }
+
+    names
+}
+
+pub fn goal_functions(config: &config::Config, environment: &Environment) -> Vec<GoalFunction> {
+    let influence_radius = InfluenceRadius(config.influence_radius);
+    let cohesion_kernel = config.cohesion_kernel;
+    let separation_kernel = config.separation_kernel;
+    let alignment_kernel = config.alignment_kernel;
+    let (wander_distance, wander_radius) = (config.wander_distance, config.wander_radius);
+    let wander_weight = config.wander_weight;
+
+    let mut goal_functions: Vec<GoalFunction> = vec![
+        //Box::new(|boid, _| boids::goals::static_goal(boid, na::vec2(0.75, 0.0))),
+        Box::new(move |_, world| {
+            boids::goals::center_of_mass(world, influence_radius, cohesion_kernel)
+        }),
+        Box::new(move |boid, world| {
+            boids::goals::keep_distance(boid, world, influence_radius, 0.025, separation_kernel)
+        }),
+        Box::new(move |_, world| {
+            boids::goals::Goal(
+                boids::goals::same_direction(world, influence_radius, alignment_kernel).0 * 1.5,
+            )
+        }),
+        Box::new(move |boid, _| {
+            boids::goals::Goal(
+                boids::goals::wander(boid, wander_distance, wander_radius).0 * wander_weight,
+            )
+        }),
+        Box::new(|boid, _| {
+            boids::goals::Goal(boids::goals::bound(boid, na::vec2(1.0, 1.0), 0.3).0 * 20.0)
+        }),
+    ];
+
+    if !environment.obstacles.is_empty() {
+        let obstacles = environment.obstacles.clone();
+        goal_functions.push(Box::new(move |boid, _| {
+            boids::goals::Goal(
+                obstacles
+                    .iter()
+                    .map(|obstacle| {
+                        boids::goals::avoid_obstacle(
+                            boid,
+                            obstacle.centre,
+                            obstacle.radius,
+                            obstacle.falloff,
+                        )
+                        .0 * obstacle.weight
+                    })
+                    .sum(),
+            )
+        }));
+    }
+    if !environment.goals.is_empty() {
+        let goal_points = environment.goals.clone();
+        goal_functions.push(Box::new(move |boid, _| {
+            boids::goals::Goal(
+                goal_points
+                    .iter()
+                    .map(|goal| boids::goals::static_goal(boid, goal.position).0 * goal.weight)
+                    .sum(),
+            )
+        }));
+    }
+
+    goal_functions
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Simulation {
+    pub world: World,
+    pub rng: generation::SimulationRng,
+    pub step: u64,
+    #[serde(default)]
+    pub environment: Environment,
+    /// The id given to the next boid spawned. Ids are never reused.
+    #[serde(default)]
+    pub next_id: u32,
+}
+
+pub fn new(config: &config::Config) -> Simulation {
+    let mut rng = generation::seeded_rng(config.seed);
+    let world = generation::spawn_world(config, &mut rng);
+
+    with_world(config, world, rng, Environment::default())
+}
+
+/// The velocity a new boid facing along `rot` starts with. Vicsek and Cucker–Smale boids need to
+/// be moving from the start, while boids start at rest and steer themselves off.
+fn initial_velocity(config: &config::Config, rot: na::Vec2) -> na::Vec2 {
+    match config.rule {
+        Rule::Boids => na::zero(),
+        Rule::Vicsek | Rule::CuckerSmale => rot * config.initial_speed,
+    }
+}
+
+/// Starts from a world that has already been placed, setting every boid off with its
+/// `initial_velocity` and numbering them from 0
+pub fn with_world(
+    config: &config::Config,
+    mut world: World,
+    rng: generation::SimulationRng,
+    environment: Environment,
+) -> Simulation {
+    for (id, boid) in world.0.iter_mut().enumerate() {
+        boid.id = id as u32;
+        boid.vel = initial_velocity(config, boid.rot);
+    }
+
+    Simulation {
+        next_id: world.0.len() as u32,
+        world,
+        rng,
+        step: 0,
+        environment,
+    }
+}
+
+impl Simulation {
+    /// Adds the boid under a fresh id, which is returned
+    pub fn spawn(&mut self, mut entity: physics::Entity) -> u32 {
+        entity.id = self.next_id;
+        self.next_id += 1;
+        self.world.0.push(entity);
+
+        entity.id
+    }
+
+    /// Spawns a boid somewhere between `min_pos` and `max_pos`, heading anywhere, with its
+    /// `initial_velocity`
+    pub fn spawn_random(
+        &mut self,
+        config: &config::Config,
+        min_pos: na::Vec2,
+        max_pos: na::Vec2,
+    ) -> u32 {
+        let mut boid = generation::random_entity(min_pos, max_pos, &mut self.rng);
+        boid.vel = initial_velocity(config, boid.rot);
+        self.spawn(boid)
+    }
+
+    pub fn despawn(&mut self, id: u32) -> Option<physics::Entity> {
+        let index = self.world.index_of(id)?;
+        Some(self.world.0.remove(index))
+    }
+
+    pub fn step(
+        &mut self,
+        config: &config::Config,
+        goal_functions: &[GoalFunction],
+        delta_time: f32,
+    ) {
+        self.step_with_pulls(config, goal_functions, &[], delta_time)
+    }
+
+    /// Steps with `pulls` added on top of the averaged goals, as `boids::step_world` describes.
+    /// Only the boids rule uses goals, so the other rules ignore them.
+    pub fn step_with_pulls(
+        &mut self,
+        config: &config::Config,
+        goal_functions: &[GoalFunction],
+        pulls: &[GoalFunction],
+        delta_time: f32,
+    ) {
+        let before = match (config.check_invariants, config.rule) {
+            (true, Rule::Boids) => Some(World(self.world.0.clone())),
+            _ => None,
+        };
+
+        match config.rule {
+            Rule::Boids => {
+                if config.behaviour_active {
+                    boids::goals::update_wander(
+                        &mut self.world,
+                        config.wander_jitter,
+                        &mut self.rng,
+                    );
+                    self.world = boids::step_world(&self.world, config, goal_functions, pulls);
+                }
+
+                physics::step_world(&mut self.world, delta_time, config);
+            }
+            Rule::Vicsek => boids::vicsek::step_world(
+                &mut self.world,
+                InfluenceRadius(config.influence_radius),
+                config.vicsek_speed,
+                config.vicsek_noise,
+                delta_time,
+                &mut self.rng,
+            ),
+            Rule::CuckerSmale => {
+                self.world = boids::cucker_smale::step_world(
+                    &self.world,
+                    config.cucker_smale_k,
+                    config.cucker_smale_beta,
+                    delta_time,
+                )
+            }
+        }
+
+        self.step += 1;
+
+        if config.check_invariants {
+            if let Err(violation) = invariants::check(
+                self.step,
+                before.as_ref(),
+                &self.world,
+                config,
+                goal_functions,
+                &goal_names(&self.environment),
+            ) {
+                panic!("{}", violation);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::test_config;
+
+    #[test]
+    fn test_only_vicsek_and_cucker_smale_start_moving() {
+        for (rule, moving) in &[
+            (Rule::Boids, false),
+            (Rule::Vicsek, true),
+            (Rule::CuckerSmale, true),
+        ] {
+            let config = config::Config {
+                rule: *rule,
+                ..test_config()
+            };
+            let mut simulation = new(&config);
+            simulation.spawn_random(&config, na::vec2(-1.0, -1.0), na::vec2(1.0, 1.0));
+
+            for boid in &simulation.world.0 {
+                assert_eq!(boid.vel != na::zero(), *moving, "{:?}", rule);
+            }
+        }
+    }
+
+    #[test]
+    fn test_pull_leaves_boids_beyond_its_falloff_alone() {
+        let config = test_config();
+        let mut released = new(&config);
+        let goal_functions = goal_functions(&config, &released.environment);
+        let mut held = new(&config);
+        let cursor = held.world.0[0].pos + na::vec2(0.1, 0.0);
+        let falloff = config.pointer_falloff;
+        let pulls: Vec<GoalFunction> = vec![Box::new(move |boid, _| {
+            boids::goals::Goal(boids::goals::static_goal_with_falloff(boid, cursor, falloff).0)
+        })];
+
+        let beyond: Vec<bool> = held
+            .world
+            .0
+            .iter()
+            .map(|boid| na::distance(&boid.pos, &cursor) >= falloff)
+            .collect();
+        assert!(beyond.contains(&true));
+
+        released.step(&config, &goal_functions, DELTA_TIME);
+        held.step_with_pulls(&config, &goal_functions, &pulls, DELTA_TIME);
+
+        for ((a, b), beyond) in released.world.0.iter().zip(&held.world.0).zip(beyond) {
+            if beyond {
+                assert_eq!(a.vel.x.to_bits(), b.vel.x.to_bits());
+                assert_eq!(a.vel.y.to_bits(), b.vel.y.to_bits());
+                assert_eq!(a.angular_vel.to_bits(), b.angular_vel.to_bits());
+            }
+        }
+        assert_ne!(released.world.0[0].vel, held.world.0[0].vel);
+    }
+
+    #[test]
+    fn test_ids_survive_steps_spawns_and_despawns() {
+        for rule in &[Rule::Boids, Rule::Vicsek, Rule::CuckerSmale] {
+            let config = config::Config {
+                rule: *rule,
+                ..test_config()
+            };
+            let mut simulation = new(&config);
+            let goal_functions = goal_functions(&config, &simulation.environment);
+            let tracked = simulation.world.0[7];
+
+            simulation.step(&config, &goal_functions, DELTA_TIME);
+            assert_eq!(simulation.despawn(3).map(|boid| boid.id), Some(3));
+            assert!(simulation.despawn(3).is_none());
+            let spawned = simulation.spawn(physics::Entity::default());
+            simulation.step(&config, &goal_functions, DELTA_TIME);
+
+            let population = config.population;
+            assert_eq!(spawned, population);
+            assert_eq!(simulation.next_id, population + 1);
+            let ids: Vec<u32> = simulation.world.0.iter().map(|boid| boid.id).collect();
+            let expected: Vec<u32> = (0..=population).filter(|&id| id != 3).collect();
+            assert_eq!(ids, expected);
+
+            assert_eq!(simulation.world.index_of(7), Some(6));
+            assert_ne!(simulation.world.0[6].pos, tracked.pos);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! The whole state of a run, saved as JSON so that it can be resumed later.
+//! A resumed run matches the original bit for bit.
+
+use crate::config;
+use crate::simulation::Simulation;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+#[derive(Serialize, Deserialize)]
+pub struct Snapshot {
+    pub config: config::Config,
+    pub simulation: Simulation,
+}
+
+/// Where the quick snapshot hotkeys save to and load from
+pub const QUICK_SNAPSHOT: &str = "quick_snapshot.json";
+
+pub fn save(path: &Path, config: &config::Config, simulation: &Simulation) -> io::Result<()> {
+    #[derive(Serialize)]
+    struct SnapshotRef<'a> {
+        config: &'a config::Config,
+        simulation: &'a Simulation,
+    }
+
+    let writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer(writer, &SnapshotRef { config, simulation }).map_err(io::Error::from)
+}
+
+pub fn load(path: &Path) -> io::Result<Snapshot> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut snapshot: Snapshot = serde_json::from_reader(reader).map_err(io::Error::from)?;
+    number_boids(&mut snapshot.simulation);
+
+    Ok(snapshot)
+}
+
+/// Snapshots from before boids had ids load with every id 0, so number them as a new world would be
+fn number_boids(simulation: &mut Simulation) {
+    if simulation.next_id == 0 && !simulation.world.0.is_empty() {
+        for (id, boid) in simulation.world.0.iter_mut().enumerate() {
+            boid.id = id as u32;
+        }
+        simulation.next_id = simulation.world.0.len() as u32;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation;
+    use crate::test_utils::test_config;
+
+    #[test]
+    fn test_loads_snapshot_from_before_ids() {
+        let config = test_config();
+        let simulation = simulation::new(&config);
+        let mut json = serde_json::to_value(&simulation).unwrap();
+        json.as_object_mut().unwrap().remove("next_id");
+        json.as_object_mut().unwrap().remove("environment");
+        for boid in json["world"].as_array_mut().unwrap() {
+            boid.as_object_mut().unwrap().remove("id");
+            boid.as_object_mut().unwrap().remove("species");
+        }
+
+        let path =
+            std::env::temp_dir().join(format!("boids_old_snapshot_{}.json", std::process::id()));
+        let old_config: toml::Value =
+            toml::from_str(include_str!("../fixtures/baseline_boids.toml")).unwrap();
+        let old = serde_json::json!({ "config": old_config, "simulation": json });
+        std::fs::write(&path, old.to_string()).unwrap();
+        let Snapshot {
+            simulation: loaded, ..
+        } = load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let ids: Vec<u32> = loaded.world.0.iter().map(|boid| boid.id).collect();
+        let expected: Vec<u32> = (0..config.population).collect();
+        assert_eq!(ids, expected);
+        assert_eq!(loaded.next_id, config.population);
+    }
+
+    #[test]
+    fn test_resume_is_bit_exact() {
+        let config = test_config();
+        let mut original = simulation::new(&config);
+        let goal_functions = simulation::goal_functions(&config, &original.environment);
+        for _ in 0..20 {
+            original.step(&config, &goal_functions, simulation::DELTA_TIME);
+        }
+
+        let path = std::env::temp_dir().join(format!("boids_snapshot_{}.json", std::process::id()));
+        save(&path, &config, &original).unwrap();
+        let Snapshot {
+            config: resumed_config,
+            simulation: mut resumed,
+        } = load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(resumed.step, 20);
+        for _ in 0..20 {
+            original.step(&config, &goal_functions, simulation::DELTA_TIME);
+            resumed.step(&resumed_config, &goal_functions, simulation::DELTA_TIME);
+        }
+
+        for (a, b) in original.world.0.iter().zip(&resumed.world.0) {
+            assert_eq!(a.pos.x.to_bits(), b.pos.x.to_bits());
+            assert_eq!(a.pos.y.to_bits(), b.pos.y.to_bits());
+            assert_eq!(a.vel.x.to_bits(), b.vel.x.to_bits());
+            assert_eq!(a.rot.y.to_bits(), b.rot.y.to_bits());
+            assert_eq!(a.wander_angle.to_bits(), b.wander_angle.to_bits());
+        }
+    }
+}
@@ -1,3 +1,5 @@
+#[cfg(test)]
+use crate::config::Config;
 use crate::physics::Entity;
 use nalgebra_glm as na;
 use proptest::prelude::*;
@@ -17,3 +19,9 @@ prop_compose! {
         }
     }
 }
+
+/// A fixed config for tests, kept apart from boids.toml so that tuning the app can't break them
+#[cfg(test)]
+pub fn test_config() -> Config {
+    toml::from_str(include_str!("../fixtures/config.toml")).unwrap()
+}
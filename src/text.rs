@@ -0,0 +1,240 @@
+//! Text drawn from a built-in 5 by 7 pixel font, as triangles in window pixels. Letters are
+//! drawn in upper case, and characters without a glyph come out as "?".
+
+use crate::geometry::Model;
+use glium::index::PrimitiveType;
+use nalgebra_glm as na;
+
+const GLYPH_WIDTH: u32 = 5;
+const GLYPH_HEIGHT: u32 = 7;
+/// Font pixels from the start of one character to the next, and from one line to the next
+const ADVANCE: u32 = GLYPH_WIDTH + 1;
+const LINE_HEIGHT: u32 = GLYPH_HEIGHT + 2;
+
+/// Rows from the top, with the leftmost pixel in the highest of the 5 bits
+fn glyph(c: char) -> [u8; 7] {
+    match c.to_ascii_uppercase() {
+        ' ' => [0, 0, 0, 0, 0, 0, 0],
+        '0' => [
+            0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110,
+        ],
+        '1' => [
+            0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110,
+        ],
+        '2' => [
+            0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111,
+        ],
+        '3' => [
+            0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110,
+        ],
+        '4' => [
+            0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010,
+        ],
+        '5' => [
+            0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110,
+        ],
+        '6' => [
+            0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110,
+        ],
+        '7' => [
+            0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000,
+        ],
+        '8' => [
+            0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110,
+        ],
+        '9' => [
+            0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100,
+        ],
+        'A' => [
+            0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001,
+        ],
+        'B' => [
+            0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110,
+        ],
+        'C' => [
+            0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110,
+        ],
+        'D' => [
+            0b11100, 0b10010, 0b10001, 0b10001, 0b10001, 0b10010, 0b11100,
+        ],
+        'E' => [
+            0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111,
+        ],
+        'F' => [
+            0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000,
+        ],
+        'G' => [
+            0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111,
+        ],
+        'H' => [
+            0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001,
+        ],
+        'I' => [
+            0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110,
+        ],
+        'J' => [
+            0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100,
+        ],
+        'K' => [
+            0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001,
+        ],
+        'L' => [
+            0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111,
+        ],
+        'M' => [
+            0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001,
+        ],
+        'N' => [
+            0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001,
+        ],
+        'O' => [
+            0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110,
+        ],
+        'P' => [
+            0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000,
+        ],
+        'Q' => [
+            0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101,
+        ],
+        'R' => [
+            0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001,
+        ],
+        'S' => [
+            0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110,
+        ],
+        'T' => [
+            0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100,
+        ],
+        'U' => [
+            0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110,
+        ],
+        'V' => [
+            0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100,
+        ],
+        'W' => [
+            0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010,
+        ],
+        'X' => [
+            0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001,
+        ],
+        'Y' => [
+            0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100,
+        ],
+        'Z' => [
+            0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111,
+        ],
+        '.' => [0, 0, 0, 0, 0, 0b01100, 0b01100],
+        ',' => [0, 0, 0, 0, 0b01100, 0b00100, 0b01000],
+        ':' => [0, 0b01100, 0b01100, 0, 0b01100, 0b01100, 0],
+        '-' => [0, 0, 0, 0b11111, 0, 0, 0],
+        '+' => [0, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0],
+        '=' => [0, 0, 0b11111, 0, 0b11111, 0, 0],
+        '/' => [0, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0],
+        '(' => [
+            0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00100, 0b00010,
+        ],
+        ')' => [
+            0b01000, 0b00100, 0b00010, 0b00010, 0b00010, 0b00100, 0b01000,
+        ],
+        '%' => [
+            0b11000, 0b11001, 0b00010, 0b00100, 0b01000, 0b10011, 0b00011,
+        ],
+        '_' => [0, 0, 0, 0, 0, 0, 0b11111],
+        _ => [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0, 0b00100],
+    }
+}
+
+/// Runs of lit pixels in a glyph row, as (first column, length)
+fn runs(row: u8) -> Vec<(u32, u32)> {
+    let lit = |column: u32| row & (1 << (GLYPH_WIDTH - 1 - column)) != 0;
+    let mut runs = Vec::new();
+    let mut column = 0;
+    while column < GLYPH_WIDTH {
+        if lit(column) {
+            let start = column;
+            while column < GLYPH_WIDTH && lit(column) {
+                column += 1;
+            }
+            runs.push((start, column - start));
+        } else {
+            column += 1;
+        }
+    }
+
+    runs
+}
+
+/// `lines` with the top left of the first at `origin`, in window pixels with y downwards.
+/// Each font pixel is `scale` window pixels square.
+pub fn text(lines: &[String], origin: na::Vec2, scale: f32, colour: na::Vec4) -> Model {
+    let mut vertices = Vec::new();
+
+    for (line_number, line) in lines.iter().enumerate() {
+        let top = origin.y + (line_number as u32 * LINE_HEIGHT) as f32 * scale;
+        for (index, c) in line.chars().enumerate() {
+            let left = origin.x + (index as u32 * ADVANCE) as f32 * scale;
+            for (row, bits) in glyph(c).iter().enumerate() {
+                for (column, length) in runs(*bits) {
+                    let x0 = left + column as f32 * scale;
+                    let x1 = x0 + length as f32 * scale;
+                    let y0 = top + row as f32 * scale;
+                    let y1 = y0 + scale;
+                    vertices.extend_from_slice(&[
+                        na::vec3(x0, y0, 1.0),
+                        na::vec3(x1, y0, 1.0),
+                        na::vec3(x1, y1, 1.0),
+                        na::vec3(x0, y0, 1.0),
+                        na::vec3(x1, y1, 1.0),
+                        na::vec3(x0, y1, 1.0),
+                    ]);
+                }
+            }
+        }
+    }
+
+    Model(vertices, PrimitiveType::TrianglesList, colour)
+}
+
+/// Window pixels taken up by the widest of `lines` and all of them stacked, at `scale`
+pub fn size(lines: &[String], scale: f32) -> na::Vec2 {
+    let columns = lines
+        .iter()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0) as f32;
+    na::vec2(
+        columns * ADVANCE as f32 * scale,
+        lines.len() as f32 * LINE_HEIGHT as f32 * scale,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runs() {
+        assert_eq!(runs(0b00000), vec![]);
+        assert_eq!(runs(0b11111), vec![(0, 5)]);
+        assert_eq!(runs(0b10011), vec![(0, 1), (3, 2)]);
+    }
+
+    #[test]
+    fn test_text_layout() {
+        let colour = na::vec4(1.0, 1.0, 1.0, 1.0);
+        let Model(vertices, _, _) = text(&["-".to_string()], na::vec2(10.0, 20.0), 2.0, colour);
+        // A single run across the middle row
+        assert_eq!(vertices.len(), 6);
+        assert_eq!(vertices[0], na::vec3(10.0, 26.0, 1.0));
+        assert_eq!(vertices[2], na::vec3(20.0, 28.0, 1.0));
+
+        let lines = vec!["a".to_string(), "  -".to_string()];
+        let Model(vertices, _, _) = text(&lines, na::zero(), 1.0, colour);
+        let lowest = vertices.iter().map(|v| v.y).fold(0.0, f32::max);
+        let rightmost = vertices.iter().map(|v| v.x).fold(0.0, f32::max);
+        assert_eq!(lowest, (LINE_HEIGHT + 4) as f32);
+        assert_eq!(rightmost, (2 * ADVANCE + GLYPH_WIDTH) as f32);
+        assert_eq!(glyph('a'), glyph('A'));
+        assert_eq!(size(&lines, 2.0), na::vec2(36.0, 36.0));
+    }
+}
@@ -0,0 +1,124 @@
+//! The recent positions of every boid, drawn as lines that fade towards the oldest position.
+
+use crate::boids::World;
+use crate::draw::{vec3_to_vertex, Vertex};
+use nalgebra_glm as na;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+pub struct Trails {
+    /// Positions kept per boid
+    pub length: usize,
+    /// Oldest position first, keyed by boid id
+    histories: HashMap<u32, VecDeque<na::Vec2>>,
+}
+
+impl Trails {
+    pub fn new(length: usize) -> Trails {
+        Trails {
+            length,
+            histories: HashMap::new(),
+        }
+    }
+
+    /// Adds every boid's current position, forgetting boids that have gone
+    pub fn record(&mut self, World(boids): &World) {
+        let live: HashSet<u32> = boids.iter().map(|boid| boid.id).collect();
+        self.histories.retain(|id, _| live.contains(id));
+
+        let length = self.length;
+        for boid in boids {
+            let history = self
+                .histories
+                .entry(boid.id)
+                .or_insert_with(|| VecDeque::with_capacity(length + 1));
+            history.push_back(boid.pos);
+            while history.len() > length {
+                history.pop_front();
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.histories.clear();
+    }
+
+    /// Vertices for a `LinesList` with one segment per pair of positions, in the boid's colour
+    /// scaled down to black at the oldest end. `colours` holds one colour per boid.
+    pub fn vertices(&self, World(boids): &World, colours: &[na::Vec4]) -> Vec<Vertex> {
+        boids
+            .iter()
+            .zip(colours)
+            .filter_map(|(boid, colour)| {
+                self.histories
+                    .get(&boid.id)
+                    .map(|history| (history, colour))
+            })
+            .flat_map(|(history, colour)| {
+                let segments = history.len().saturating_sub(1);
+                history.iter().zip(history.iter().skip(1)).enumerate().map(
+                    move |(i, (from, to))| {
+                        let fade = (i + 1) as f32 / segments as f32;
+                        let colour =
+                            na::vec4(colour.x * fade, colour.y * fade, colour.z * fade, colour.w);
+                        [
+                            vec3_to_vertex(&na::vec3(from.x, from.y, 1.0), &colour),
+                            vec3_to_vertex(&na::vec3(to.x, to.y, 1.0), &colour),
+                        ]
+                    },
+                )
+            })
+            .flatten()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::Entity;
+
+    fn world(ids: &[u32], x: f32) -> World {
+        World(
+            ids.iter()
+                .map(|&id| Entity {
+                    id,
+                    pos: na::vec2(x, id as f32),
+                    ..Default::default()
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_history_is_bounded_and_follows_ids() {
+        let mut trails = Trails::new(3);
+        for step in 0..5 {
+            trails.record(&world(&[1, 2], step as f32));
+        }
+
+        let history: Vec<f32> = trails.histories[&2].iter().map(|pos| pos.x).collect();
+        assert_eq!(history, vec![2.0, 3.0, 4.0]);
+
+        trails.record(&world(&[2, 7], 5.0));
+        assert!(!trails.histories.contains_key(&1));
+        assert_eq!(trails.histories[&7].len(), 1);
+        assert_eq!(trails.histories[&2].back(), Some(&na::vec2(5.0, 2.0)));
+    }
+
+    #[test]
+    fn test_vertices_fade_towards_oldest() {
+        let mut trails = Trails::new(4);
+        for step in 0..4 {
+            trails.record(&world(&[0], step as f32));
+        }
+
+        let colour = na::vec4(1.0, 0.5, 0.0, 1.0);
+        let vertices = trails.vertices(&world(&[0], 3.0), &[colour]);
+
+        assert_eq!(vertices.len(), 6);
+        assert_eq!(vertices[0].colour, vertices[1].colour);
+        assert!(vertices[0].colour[0] < vertices[2].colour[0]);
+        assert_eq!(vertices[5].colour, [1.0, 0.5, 0.0, 1.0]);
+        assert_eq!(vertices[0].position[0], 0.0);
+    }
+}
@@ -29,6 +29,25 @@ pub fn window_closed(event: &glutin::Event) -> bool {
     }
 }
 
+pub fn key_pressed(event: &glutin::Event) -> Option<glutin::VirtualKeyCode> {
+    match event {
+        glutin::Event::WindowEvent {
+            event:
+                glutin::WindowEvent::KeyboardInput {
+                    input:
+                        glutin::KeyboardInput {
+                            state: glutin::ElementState::Pressed,
+                            virtual_keycode,
+                            ..
+                        },
+                    ..
+                },
+            ..
+        } => *virtual_keycode,
+        _ => None,
+    }
+}
+
 pub fn mean(vectors: &[na::Vec2]) -> na::Vec2 {
     let sum: na::Vec2 = vectors.iter().sum();
     let length: f32 = vectors.len() as f32;
@@ -39,6 +58,26 @@ pub fn mean(vectors: &[na::Vec2]) -> na::Vec2 {
     }
 }
 
+/// The weighted mean of `vectors`, except that a total weight under 1 isn't normalised away.
+/// A single vector at weight 0.2 comes out at a fifth of its length, so the mean fades in
+/// as vectors gain weight rather than jumping to full length with the first one.
+pub fn faded_weighted_mean(vectors: &[na::Vec2], weights: &[f32]) -> na::Vec2 {
+    let sum: na::Vec2 = vectors.iter().zip(weights).map(|(v, w)| v * *w).sum();
+    let total_weight: f32 = weights.iter().sum();
+
+    sum / total_weight.max(1.0)
+}
+
+/// The unit vector along `vec`, or zero if it has no length, rather than NaN
+pub fn normalize_or_zero(vec: &na::Vec2) -> na::Vec2 {
+    let magnitude = na::magnitude(vec);
+    if magnitude > 0.0 && magnitude.is_finite() {
+        vec / magnitude
+    } else {
+        na::zero()
+    }
+}
+
 pub fn clockwise_angle_from_j_hat(vec: &na::Vec2) -> f32 {
     if vec == &na::zero() {
         return 0.0;